@@ -0,0 +1,492 @@
+use std::cmp;
+use std::ops::Range;
+
+use cgmath::BaseFloat;
+
+use {BoundingBox, FrustumCuller, Intersection, Sphere};
+
+/// Number of volumes whose state is kept on the stack while running a batch.
+const CHUNK: usize = 64;
+
+/// The volume failed the test against the outer side of some plane.
+const STATE_OUTSIDE: u8 = 1 << 1;
+/// The volume isn't fully on the inner side of some plane.
+const STATE_PARTIAL: u8 = 1 << 0;
+
+/// Structure-of-arrays layout of a list of axis aligned bounding boxes. All the slices must have
+/// the same length.
+#[derive(Debug, Copy, Clone)]
+pub struct BoundingBoxes<'a, S: 'a> {
+    pub min_x: &'a [S],
+    pub min_y: &'a [S],
+    pub min_z: &'a [S],
+    pub max_x: &'a [S],
+    pub max_y: &'a [S],
+    pub max_z: &'a [S],
+}
+
+impl<'a, S> BoundingBoxes<'a, S> {
+    /// Returns the number of boxes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices don't have the same length.
+    pub fn len(&self) -> usize {
+        let len = self.min_x.len();
+        assert!(
+            self.min_y.len() == len && self.min_z.len() == len && self.max_x.len() == len
+                && self.max_y.len() == len && self.max_z.len() == len,
+            "bounding box slices must have the same length"
+        );
+        len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Structure-of-arrays layout of a list of spheres. All the slices must have the same length.
+#[derive(Debug, Copy, Clone)]
+pub struct Spheres<'a, S: 'a> {
+    pub center_x: &'a [S],
+    pub center_y: &'a [S],
+    pub center_z: &'a [S],
+    pub radius: &'a [S],
+}
+
+impl<'a, S> Spheres<'a, S> {
+    /// Returns the number of spheres.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the slices don't have the same length.
+    pub fn len(&self) -> usize {
+        let len = self.center_x.len();
+        assert!(
+            self.center_y.len() == len && self.center_z.len() == len && self.radius.len() == len,
+            "sphere slices must have the same length"
+        );
+        len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<S: BaseFloat> FrustumCuller<S> {
+    /// Tests a list of bounding boxes, writing the result of each one into `out`. The results are
+    /// the same as the ones of `test_bounding_box`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `boxes` and `out` don't have the same length.
+    pub fn cull_boxes(&self, boxes: &[BoundingBox<S>], out: &mut [Intersection]) {
+        assert_eq!(boxes.len(), out.len());
+        self.batch(
+            boxes.len(),
+            |range, plane, states| {
+                for (aab, state) in boxes[range].iter().zip(states) {
+                    *state |= box_state(plane, aab);
+                }
+            },
+            |i, intersection| out[i] = intersection,
+        );
+    }
+
+    /// Tests a list of spheres, writing the result of each one into `out`. The results are the
+    /// same as the ones of `test_sphere`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `spheres` and `out` don't have the same length.
+    pub fn cull_spheres(&self, spheres: &[Sphere<S>], out: &mut [Intersection]) {
+        assert_eq!(spheres.len(), out.len());
+        self.batch(
+            spheres.len(),
+            |range, plane, states| {
+                for (sphere, state) in spheres[range].iter().zip(states) {
+                    *state |= sphere_state(plane, sphere);
+                }
+            },
+            |i, intersection| out[i] = intersection,
+        );
+    }
+
+    /// Same as `cull_boxes`, taking the boxes in structure-of-arrays layout.
+    pub fn cull_boxes_soa(&self, boxes: BoundingBoxes<S>, out: &mut [Intersection]) {
+        assert_eq!(boxes.len(), out.len());
+        self.batch(
+            boxes.len(),
+            |range, plane, states| boxes_state(plane, &boxes, range, states),
+            |i, intersection| out[i] = intersection,
+        );
+    }
+
+    /// Same as `cull_spheres`, taking the spheres in structure-of-arrays layout.
+    pub fn cull_spheres_soa(&self, spheres: Spheres<S>, out: &mut [Intersection]) {
+        assert_eq!(spheres.len(), out.len());
+        self.batch(
+            spheres.len(),
+            |range, plane, states| spheres_state(plane, &spheres, range, states),
+            |i, intersection| out[i] = intersection,
+        );
+    }
+
+    /// Writes the indices of the boxes that are at least partially inside of the frustum at the
+    /// front of `out`, in increasing order, and returns how many of them were written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` is shorter than `boxes`.
+    pub fn visible_boxes(&self, boxes: &[BoundingBox<S>], out: &mut [usize]) -> usize {
+        assert!(out.len() >= boxes.len());
+        let mut count = 0;
+        self.batch(
+            boxes.len(),
+            |range, plane, states| {
+                for (aab, state) in boxes[range].iter().zip(states) {
+                    *state |= box_state(plane, aab);
+                }
+            },
+            |i, intersection| compact(out, &mut count, i, intersection),
+        );
+        count
+    }
+
+    /// Writes the indices of the spheres that are at least partially inside of the frustum at the
+    /// front of `out`, in increasing order, and returns how many of them were written.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `out` is shorter than `spheres`.
+    pub fn visible_spheres(&self, spheres: &[Sphere<S>], out: &mut [usize]) -> usize {
+        assert!(out.len() >= spheres.len());
+        let mut count = 0;
+        self.batch(
+            spheres.len(),
+            |range, plane, states| {
+                for (sphere, state) in spheres[range].iter().zip(states) {
+                    *state |= sphere_state(plane, sphere);
+                }
+            },
+            |i, intersection| compact(out, &mut count, i, intersection),
+        );
+        count
+    }
+
+    /// Same as `visible_boxes`, taking the boxes in structure-of-arrays layout.
+    pub fn visible_boxes_soa(&self, boxes: BoundingBoxes<S>, out: &mut [usize]) -> usize {
+        assert!(out.len() >= boxes.len());
+        let mut count = 0;
+        self.batch(
+            boxes.len(),
+            |range, plane, states| boxes_state(plane, &boxes, range, states),
+            |i, intersection| compact(out, &mut count, i, intersection),
+        );
+        count
+    }
+
+    /// Same as `visible_spheres`, taking the spheres in structure-of-arrays layout.
+    pub fn visible_spheres_soa(&self, spheres: Spheres<S>, out: &mut [usize]) -> usize {
+        assert!(out.len() >= spheres.len());
+        let mut count = 0;
+        self.batch(
+            spheres.len(),
+            |range, plane, states| spheres_state(plane, &spheres, range, states),
+            |i, intersection| compact(out, &mut count, i, intersection),
+        );
+        count
+    }
+
+    /// Runs `test` on `len` volumes, in chunks, one plane at a time. `test` is given the range of
+    /// volumes of the chunk and the plane, and has to accumulate the state of each volume into
+    /// the corresponding element of the slice, so that its loop only depends on the volume index
+    /// and can be vectorized. `emit` is called with the final result of each volume, in
+    /// increasing order.
+    fn batch<T, E>(&self, len: usize, mut test: T, mut emit: E)
+    where
+        T: FnMut(Range<usize>, (S, S, S, S), &mut [u8]),
+        E: FnMut(usize, Intersection),
+    {
+        let mut states = [0u8; CHUNK];
+        let mut start = 0;
+        while start < len {
+            let end = cmp::min(start + CHUNK, len);
+            let states = &mut states[..end - start];
+            for state in states.iter_mut() {
+                *state = 0;
+            }
+
+            let tested = self.plane_mask();
+            for plane in (0..6).filter(|plane| tested & (1 << plane) != 0) {
+                test(start..end, self.plane_coefficients(plane), states);
+            }
+
+            for (i, &state) in states.iter().enumerate() {
+                emit(start + i, intersection(state));
+            }
+            start = end;
+        }
+    }
+}
+
+/// Accumulates the state of the boxes in `range` against a plane. The corners tested against it
+/// are chosen once for the whole range, from the signs of the components of its normal.
+#[inline]
+fn boxes_state<S: BaseFloat>(
+    (a, b, c, d): (S, S, S, S),
+    boxes: &BoundingBoxes<S>,
+    range: Range<usize>,
+    states: &mut [u8],
+) {
+    let n = states.len();
+    let (px, nx) = if a < S::zero() {
+        (&boxes.min_x[range.clone()], &boxes.max_x[range.clone()])
+    } else {
+        (&boxes.max_x[range.clone()], &boxes.min_x[range.clone()])
+    };
+    let (py, ny) = if b < S::zero() {
+        (&boxes.min_y[range.clone()], &boxes.max_y[range.clone()])
+    } else {
+        (&boxes.max_y[range.clone()], &boxes.min_y[range.clone()])
+    };
+    let (pz, nz) = if c < S::zero() {
+        (&boxes.min_z[range.clone()], &boxes.max_z[range.clone()])
+    } else {
+        (&boxes.max_z[range.clone()], &boxes.min_z[range])
+    };
+
+    // slicing everything to the same length lets the bounds checks be hoisted out of the loop
+    let (px, py, pz) = (&px[..n], &py[..n], &pz[..n]);
+    let (nx, ny, nz) = (&nx[..n], &ny[..n], &nz[..n]);
+    for i in 0..n {
+        let visible = a * px[i] + b * py[i] + c * pz[i] >= -d;
+        let inside = a * nx[i] + b * ny[i] + c * nz[i] >= -d;
+        states[i] |= state(visible, inside);
+    }
+}
+
+/// Accumulates the state of the spheres in `range` against a plane.
+#[inline]
+fn spheres_state<S: BaseFloat>(
+    (a, b, c, d): (S, S, S, S),
+    spheres: &Spheres<S>,
+    range: Range<usize>,
+    states: &mut [u8],
+) {
+    let n = states.len();
+    let x = &spheres.center_x[range.clone()][..n];
+    let y = &spheres.center_y[range.clone()][..n];
+    let z = &spheres.center_z[range.clone()][..n];
+    let radius = &spheres.radius[range][..n];
+    for i in 0..n {
+        let dist = a * x[i] + b * y[i] + c * z[i] + d;
+        let visible = dist >= -radius[i];
+        let inside = dist >= radius[i];
+        states[i] |= state(visible, inside);
+    }
+}
+
+#[inline]
+fn box_state<S: BaseFloat>((a, b, c, d): (S, S, S, S), aab: &BoundingBox<S>) -> u8 {
+    let (px, nx) = if a < S::zero() {
+        (aab.min.x, aab.max.x)
+    } else {
+        (aab.max.x, aab.min.x)
+    };
+    let (py, ny) = if b < S::zero() {
+        (aab.min.y, aab.max.y)
+    } else {
+        (aab.max.y, aab.min.y)
+    };
+    let (pz, nz) = if c < S::zero() {
+        (aab.min.z, aab.max.z)
+    } else {
+        (aab.max.z, aab.min.z)
+    };
+
+    let visible = a * px + b * py + c * pz >= -d;
+    let inside = a * nx + b * ny + c * nz >= -d;
+    state(visible, inside)
+}
+
+#[inline]
+fn sphere_state<S: BaseFloat>((a, b, c, d): (S, S, S, S), sphere: &Sphere<S>) -> u8 {
+    let dist = a * sphere.center.x + b * sphere.center.y + c * sphere.center.z + d;
+    let visible = dist >= -sphere.radius;
+    let inside = dist >= sphere.radius;
+    state(visible, inside)
+}
+
+#[inline]
+fn state(visible: bool, inside: bool) -> u8 {
+    (!visible as u8) << 1 | (!inside as u8)
+}
+
+#[inline]
+fn intersection(state: u8) -> Intersection {
+    if state & STATE_OUTSIDE != 0 {
+        Intersection::Outside
+    } else if state & STATE_PARTIAL != 0 {
+        Intersection::Partial
+    } else {
+        Intersection::Inside
+    }
+}
+
+#[inline]
+fn compact(out: &mut [usize], count: &mut usize, i: usize, intersection: Intersection) {
+    if intersection != Intersection::Outside {
+        out[*count] = i;
+        *count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use {BoundingBox, FrustumCuller, Intersection, Sphere};
+
+    use super::{BoundingBoxes, Spheres};
+
+    use cgmath::{PerspectiveFov, Rad, Vector3};
+
+    use std::f64;
+
+    fn culler() -> FrustumCuller<f64> {
+        FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        })
+    }
+
+    // a grid of volumes that covers the inside, the outside and the boundary of the frustum
+    fn positions() -> Vec<Vector3<f64>> {
+        let mut positions = Vec::new();
+        for x in -10..10 {
+            for y in -3..3 {
+                for z in -6..2 {
+                    positions.push(Vector3::new(x as f64, y as f64 * 2.0, z as f64 * 3.0));
+                }
+            }
+        }
+        positions
+    }
+
+    #[test]
+    fn test_cull_boxes() {
+        let c = culler();
+        let boxes: Vec<_> = positions()
+            .into_iter()
+            .map(|p| BoundingBox::from_params(p, p + Vector3::new(0.5, 1.0, 1.5)))
+            .collect();
+
+        let mut out = vec![Intersection::Outside; boxes.len()];
+        c.cull_boxes(&boxes, &mut out);
+        for (aab, intersection) in boxes.iter().zip(out.iter()) {
+            assert_eq!(c.test_bounding_box(*aab), *intersection);
+        }
+        assert!(out.contains(&Intersection::Inside));
+        assert!(out.contains(&Intersection::Partial));
+        assert!(out.contains(&Intersection::Outside));
+
+        let min_x: Vec<_> = boxes.iter().map(|b| b.min.x).collect();
+        let min_y: Vec<_> = boxes.iter().map(|b| b.min.y).collect();
+        let min_z: Vec<_> = boxes.iter().map(|b| b.min.z).collect();
+        let max_x: Vec<_> = boxes.iter().map(|b| b.max.x).collect();
+        let max_y: Vec<_> = boxes.iter().map(|b| b.max.y).collect();
+        let max_z: Vec<_> = boxes.iter().map(|b| b.max.z).collect();
+        let soa = BoundingBoxes {
+            min_x: &min_x,
+            min_y: &min_y,
+            min_z: &min_z,
+            max_x: &max_x,
+            max_y: &max_y,
+            max_z: &max_z,
+        };
+
+        let mut out_soa = vec![Intersection::Outside; boxes.len()];
+        c.cull_boxes_soa(soa, &mut out_soa);
+        assert_eq!(out, out_soa);
+
+        let expected: Vec<_> = (0..boxes.len())
+            .filter(|&i| out[i] != Intersection::Outside)
+            .collect();
+        let mut indices = vec![0; boxes.len()];
+        let count = c.visible_boxes(&boxes, &mut indices);
+        assert_eq!(expected, &indices[..count]);
+        let count = c.visible_boxes_soa(soa, &mut indices);
+        assert_eq!(expected, &indices[..count]);
+
+        // a last chunk that is only partially filled
+        let len = boxes.len() - 7;
+        let partial = BoundingBoxes {
+            min_x: &min_x[..len],
+            min_y: &min_y[..len],
+            min_z: &min_z[..len],
+            max_x: &max_x[..len],
+            max_y: &max_y[..len],
+            max_z: &max_z[..len],
+        };
+        c.cull_boxes_soa(partial, &mut out_soa[..len]);
+        assert_eq!(out[..len], out_soa[..len]);
+    }
+
+    #[test]
+    fn test_cull_spheres() {
+        let c = culler();
+        let spheres: Vec<_> = positions()
+            .into_iter()
+            .map(|p| Sphere::from_params(p, 0.75))
+            .collect();
+
+        let mut out = vec![Intersection::Outside; spheres.len()];
+        c.cull_spheres(&spheres, &mut out);
+        for (sphere, intersection) in spheres.iter().zip(out.iter()) {
+            assert_eq!(c.test_sphere(*sphere), *intersection);
+        }
+
+        let center_x: Vec<_> = spheres.iter().map(|s| s.center.x).collect();
+        let center_y: Vec<_> = spheres.iter().map(|s| s.center.y).collect();
+        let center_z: Vec<_> = spheres.iter().map(|s| s.center.z).collect();
+        let radius: Vec<_> = spheres.iter().map(|s| s.radius).collect();
+        let soa = Spheres {
+            center_x: &center_x,
+            center_y: &center_y,
+            center_z: &center_z,
+            radius: &radius,
+        };
+
+        let mut out_soa = vec![Intersection::Outside; spheres.len()];
+        c.cull_spheres_soa(soa, &mut out_soa);
+        assert_eq!(out, out_soa);
+
+        let expected: Vec<_> = (0..spheres.len())
+            .filter(|&i| out[i] != Intersection::Outside)
+            .collect();
+        let mut indices = vec![0; spheres.len()];
+        let count = c.visible_spheres(&spheres, &mut indices);
+        assert_eq!(expected, &indices[..count]);
+        let count = c.visible_spheres_soa(soa, &mut indices);
+        assert_eq!(expected, &indices[..count]);
+
+        // a last chunk that is only partially filled
+        let len = spheres.len() - 7;
+        let partial = Spheres {
+            center_x: &center_x[..len],
+            center_y: &center_y[..len],
+            center_z: &center_z[..len],
+            radius: &radius[..len],
+        };
+        c.cull_spheres_soa(partial, &mut out_soa[..len]);
+        assert_eq!(out[..len], out_soa[..len]);
+    }
+}
@@ -0,0 +1,274 @@
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+use std::ops::Range;
+use std::slice;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use cgmath::{BaseFloat, Vector3};
+
+use {BoundingBox, FrustumCuller, Intersection, PLANE_MASK_ALL};
+
+/// Maximum number of items stored in a leaf node.
+const LEAF_SIZE: usize = 4;
+
+/// Bounding volume hierarchy of axis aligned bounding boxes, each one of them paired with a value.
+///
+/// The hierarchy is built once, splitting the items at the median of the longest axis of their
+/// centers, and is meant to be queried with `cull`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Bvh<S, T> {
+    nodes: Vec<Node<S>>,
+    items: Vec<(BoundingBox<S>, T)>,
+}
+
+#[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Node<S> {
+    bounds: BoundingBox<S>,
+    /// first item of a leaf node
+    start: usize,
+    /// number of items of a leaf node, or zero for an inner node
+    count: usize,
+    /// index of the right child of an inner node (the left child follows its parent)
+    right: usize,
+}
+
+impl<S: BaseFloat, T> Bvh<S, T> {
+    /// Builds the hierarchy from a list of items.
+    pub fn new(items: Vec<(BoundingBox<S>, T)>) -> Self {
+        let mut bvh = Self {
+            nodes: Vec::new(),
+            items,
+        };
+        if !bvh.items.is_empty() {
+            let len = bvh.items.len();
+            bvh.nodes.reserve(2 * len / LEAF_SIZE + 1);
+            bvh.build(0, len);
+        }
+        bvh
+    }
+
+    /// Returns the number of items.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the bounding box that encloses all the items, if there are any.
+    #[inline]
+    pub fn bounds(&self) -> Option<BoundingBox<S>> {
+        self.nodes.first().map(|node| node.bounds)
+    }
+
+    /// Iterates over all the items, in no particular order.
+    #[inline]
+    pub fn iter(&self) -> slice::Iter<'_, (BoundingBox<S>, T)> {
+        self.items.iter()
+    }
+
+    /// Returns an iterator over the values whose bounding box is at least partially inside of the
+    /// frustum.
+    ///
+    /// Subtrees that are fully inside of the frustum are accepted without testing their items, and
+    /// the planes a node is fully inside of are not tested again on its descendants.
+    pub fn cull<'a>(&'a self, culler: &'a FrustumCuller<S>) -> Cull<'a, S, T> {
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push((0, PLANE_MASK_ALL));
+        }
+        Cull {
+            bvh: self,
+            culler,
+            stack,
+            items: 0..0,
+            mask: 0,
+        }
+    }
+
+    /// Builds the subtree of the items in `start..end`, returning the index of its root node.
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let index = self.nodes.len();
+        let bounds = enclose(self.items[start..end].iter().map(|item| item.0));
+        self.nodes.push(Node {
+            bounds,
+            start,
+            count: end - start,
+            right: 0,
+        });
+        if end - start <= LEAF_SIZE {
+            return index;
+        }
+
+        let centers = enclose(self.items[start..end].iter().map(|item| {
+            let center = center(&item.0);
+            BoundingBox::from_params(center, center)
+        }));
+        let extent = centers.max - centers.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = start + (end - start) / 2;
+        self.items[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            center(&a.0)[axis]
+                .partial_cmp(&center(&b.0)[axis])
+                .unwrap_or(Ordering::Equal)
+        });
+
+        self.build(start, mid);
+        let right = self.build(mid, end);
+        self.nodes[index].count = 0;
+        self.nodes[index].right = right;
+        index
+    }
+}
+
+impl<S: BaseFloat, T> FromIterator<(BoundingBox<S>, T)> for Bvh<S, T> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (BoundingBox<S>, T)>,
+    {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+/// Iterator over the values of a `Bvh` that are visible from a frustum, returned by `Bvh::cull`.
+#[derive(Debug)]
+pub struct Cull<'a, S: 'a, T: 'a> {
+    bvh: &'a Bvh<S, T>,
+    culler: &'a FrustumCuller<S>,
+    /// nodes left to visit, with the mask of planes they still have to be tested against
+    stack: Vec<(usize, u8)>,
+    /// items of the leaf being visited
+    items: Range<usize>,
+    /// mask of planes the items of the current leaf still have to be tested against
+    mask: u8,
+}
+
+impl<'a, S: BaseFloat, T> Iterator for Cull<'a, S, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        loop {
+            for i in self.items.by_ref() {
+                let (ref aab, ref value) = self.bvh.items[i];
+                if self.mask == 0
+                    || self.culler.test_bounding_box_masked(*aab, self.mask).0
+                        != Intersection::Outside
+                {
+                    return Some(value);
+                }
+            }
+
+            let (index, mask) = self.stack.pop()?;
+            let node = &self.bvh.nodes[index];
+            let (intersection, mask) = self.culler.test_bounding_box_masked(node.bounds, mask);
+            if intersection == Intersection::Outside {
+                continue;
+            }
+
+            if node.count > 0 {
+                self.items = node.start..node.start + node.count;
+                self.mask = mask;
+            } else {
+                self.stack.push((node.right, mask));
+                self.stack.push((index + 1, mask));
+            }
+        }
+    }
+}
+
+#[inline]
+fn center<S: BaseFloat>(aab: &BoundingBox<S>) -> Vector3<S> {
+    (aab.min + aab.max) * (S::one() / (S::one() + S::one()))
+}
+
+/// Returns the bounding box that encloses all the given boxes. There must be at least one of them.
+fn enclose<S, I>(mut boxes: I) -> BoundingBox<S>
+where
+    S: BaseFloat,
+    I: Iterator<Item = BoundingBox<S>>,
+{
+    let first = boxes.next().expect("at least one bounding box");
+    boxes.fold(first, |acc, aab| {
+        BoundingBox::from_params(
+            Vector3::new(
+                acc.min.x.min(aab.min.x),
+                acc.min.y.min(aab.min.y),
+                acc.min.z.min(aab.min.z),
+            ),
+            Vector3::new(
+                acc.max.x.max(aab.max.x),
+                acc.max.y.max(aab.max.y),
+                acc.max.z.max(aab.max.z),
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use {BoundingBox, FrustumCuller, Intersection};
+
+    use super::Bvh;
+
+    use cgmath::{PerspectiveFov, Rad, Vector3};
+
+    use std::f64;
+
+    #[test]
+    fn test_cull_matches_brute_force() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        let mut items = Vec::new();
+        for x in -20..20 {
+            for y in -4..4 {
+                for z in -30..4 {
+                    let min = Vector3::new(x as f64 * 2.0, y as f64 * 3.0, z as f64 * 4.0);
+                    let aab = BoundingBox::from_params(min, min + Vector3::new(1.0, 2.0, 3.0));
+                    items.push((aab, items.len()));
+                }
+            }
+        }
+
+        let mut expected: Vec<_> = items
+            .iter()
+            .filter(|&&(aab, _)| c.test_bounding_box(aab) != Intersection::Outside)
+            .map(|&(_, id)| id)
+            .collect();
+        expected.sort();
+        assert!(!expected.is_empty() && expected.len() < items.len());
+
+        let bvh: Bvh<_, _> = items.into_iter().collect();
+        let mut visible: Vec<_> = bvh.cull(&c).cloned().collect();
+        visible.sort();
+        assert_eq!(expected, visible);
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh: Bvh<f32, ()> = Bvh::new(Vec::new());
+        assert!(bvh.is_empty());
+        assert_eq!(None, bvh.bounds());
+        assert_eq!(0, bvh.cull(&FrustumCuller::new()).count());
+    }
+}
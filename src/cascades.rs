@@ -0,0 +1,323 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use cgmath::{BaseFloat, Matrix4, Ortho, PerspectiveFov, Point3, Vector3, prelude::*};
+
+use float;
+use {FrustumCuller, FrustumError, Sphere};
+
+/// How the depth range of the camera is divided between the cascades.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum SplitScheme<S> {
+    /// Every cascade covers the same depth range.
+    Uniform,
+    /// Each cascade covers the same ratio between its far and near distances, which matches the
+    /// way the size of the texels on screen grows with the distance.
+    Logarithmic,
+    /// Blend of both schemes. A weight of `0` gives the uniform splits, and a weight of `1` the
+    /// logarithmic ones.
+    Practical(S),
+}
+
+/// Settings of a set of cascaded shadow maps for a directional light.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cascades<S> {
+    /// number of cascades
+    pub count: usize,
+    /// how the depth range of the camera is split
+    pub scheme: SplitScheme<S>,
+    /// direction the light travels in, in world space
+    pub light_direction: Vector3<S>,
+    /// width and height of the shadow map of each cascade, in texels
+    pub resolution: u32,
+    /// how far the light volumes extend towards the light beyond the cascades, so that objects
+    /// outside of the view of the camera still cast shadows into it
+    pub caster_distance: S,
+}
+
+/// One of the cascades computed by `Cascades::compute`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cascade<S> {
+    /// distance from the camera to the near plane of the cascade
+    pub near: S,
+    /// distance from the camera to the far plane of the cascade
+    pub far: S,
+    /// corners of the part of the view frustum covered by the cascade, in world space and in the
+    /// order of `FrustumCuller::corners`
+    pub corners: [Vector3<S>; 8],
+    /// sphere that contains the corners, in world space. Its radius only depends on the
+    /// projection of the camera, so it doesn't change as the camera moves or turns.
+    pub bounding_sphere: Sphere<S>,
+    /// orthographic view-projection matrix of the light, to render the shadow map with
+    pub light_matrix: Matrix4<S>,
+    /// culler of the light volume, to select the shadow casters of the cascade
+    pub culler: FrustumCuller<S>,
+}
+
+impl<S: BaseFloat> Cascades<S> {
+    /// Returns the distances from the camera where the cascades start and end: `count + 1`
+    /// values going from `near` to `far`, or an empty vector if `count` is zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `near` isn't positive with the `Logarithmic` and `Practical` schemes, which divide
+    /// the depth range in ratios of `far / near`.
+    pub fn split_distances(&self, near: S, far: S) -> Vec<S> {
+        if let SplitScheme::Logarithmic | SplitScheme::Practical(_) = self.scheme {
+            assert!(near > S::zero(), "near distance of logarithmic splits must be positive");
+        }
+        if self.count == 0 {
+            return Vec::new();
+        }
+
+        let count = S::from(self.count).unwrap();
+        let split = |i: usize| {
+            let p = S::from(i).unwrap() / count;
+            let uniform = near + (far - near) * p;
+            let logarithmic = near * float::powf(far / near, p);
+            match self.scheme {
+                SplitScheme::Uniform => uniform,
+                SplitScheme::Logarithmic => logarithmic,
+                SplitScheme::Practical(lambda) => {
+                    logarithmic * lambda + uniform * (S::one() - lambda)
+                }
+            }
+        };
+
+        let mut distances: Vec<S> = (0..self.count + 1).map(split).collect();
+        distances[0] = near;
+        distances[self.count] = far;
+        distances
+    }
+
+    /// Computes the cascades of a camera with the given projection and view matrix.
+    ///
+    /// The light volume of each cascade is an orthographic projection around its bounding
+    /// sphere, which is moved in steps of whole texels of the shadow map. Together with the
+    /// constant radius of the sphere, this keeps the shadows from shimmering as the camera moves.
+    /// The shadow casters of a cascade are the objects that pass the tests of its culler.
+    ///
+    /// Fails if the corners of the cascades can't be computed (see `FrustumCuller::corners`),
+    /// such as for a singular view matrix.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the resolution is less than 3 texels.
+    pub fn compute(
+        &self,
+        perspective: PerspectiveFov<S>,
+        view: Matrix4<S>,
+    ) -> Result<Vec<Cascade<S>>, FrustumError> {
+        assert!(self.resolution > 2, "shadow map resolution is too small");
+
+        let direction = self.light_direction / float::sqrt(self.light_direction.magnitude2());
+        let up = if direction.y.abs() < S::from(0.9).unwrap() {
+            Vector3::unit_y()
+        } else {
+            Vector3::unit_z()
+        };
+        let light_view = Matrix4::look_at_dir(Point3::origin(), direction, up);
+
+        // the center of the sphere moves by up to one texel, so the light volume is grown by as
+        // much to keep containing it
+        let resolution = S::from(self.resolution).unwrap();
+        let two = S::one() + S::one();
+        let scale = resolution / (resolution - two);
+
+        let distances = self.split_distances(perspective.near, perspective.far);
+        let mut cascades = Vec::with_capacity(self.count);
+        for range in distances.windows(2) {
+            let (near, far) = (range[0], range[1]);
+            let projection = PerspectiveFov {
+                near,
+                far,
+                ..perspective
+            };
+            let corners = FrustumCuller::from_matrix(Matrix4::from(projection) * view).corners()?;
+
+            // the sphere is computed in view space, so that its radius doesn't depend on the view
+            // matrix, and its center is then placed at the same depth in world space
+            let sphere = FrustumCuller::from_perspective_fov(projection).bounding_sphere()?;
+            let t = (-sphere.center.z - near) / (far - near);
+            let quarter = S::from(0.25).unwrap();
+            let near_center = (corners[0] + corners[1] + corners[2] + corners[3]) * quarter;
+            let far_center = (corners[4] + corners[5] + corners[6] + corners[7]) * quarter;
+            let center = near_center + (far_center - near_center) * t;
+            let bounding_sphere = Sphere::from_params(center, sphere.radius);
+
+            let half_size = sphere.radius * scale;
+            let texel = two * half_size / resolution;
+            let light_center = light_view.transform_point(Point3::from_vec(center));
+            let x = float::floor(light_center.x / texel) * texel;
+            let y = float::floor(light_center.y / texel) * texel;
+            let depth = -light_center.z;
+            let light_matrix = Matrix4::from(Ortho {
+                left: x - half_size,
+                right: x + half_size,
+                bottom: y - half_size,
+                top: y + half_size,
+                near: depth - sphere.radius - self.caster_distance,
+                far: depth + sphere.radius,
+            }) * light_view;
+
+            cascades.push(Cascade {
+                near,
+                far,
+                corners,
+                bounding_sphere,
+                light_matrix,
+                culler: FrustumCuller::from_matrix(light_matrix),
+            });
+        }
+        Ok(cascades)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {BoundingBox, Intersection, Sphere};
+
+    use super::{Cascades, SplitScheme};
+
+    use cgmath::{Matrix4, PerspectiveFov, Point3, Rad, Vector3, prelude::*};
+
+    use std::f64;
+
+    fn perspective() -> PerspectiveFov<f64> {
+        PerspectiveFov {
+            fovy: Rad(1.0),
+            aspect: 1.5,
+            near: 0.5,
+            far: 200.0,
+        }
+    }
+
+    fn cascades(scheme: SplitScheme<f64>) -> Cascades<f64> {
+        Cascades {
+            count: 4,
+            scheme,
+            light_direction: Vector3::new(-1.0, -3.0, 0.5),
+            resolution: 1024,
+            caster_distance: 50.0,
+        }
+    }
+
+    #[test]
+    fn test_split_distances() {
+        let uniform = cascades(SplitScheme::Uniform).split_distances(1.0, 81.0);
+        assert_eq!(vec![1.0, 21.0, 41.0, 61.0, 81.0], uniform);
+
+        let logarithmic = cascades(SplitScheme::Logarithmic).split_distances(1.0, 81.0);
+        assert_eq!(5, logarithmic.len());
+        for (distance, expected) in logarithmic.iter().zip([1.0, 3.0, 9.0, 27.0, 81.0].iter()) {
+            assert_relative_eq!(expected, distance, epsilon = 1e-9);
+        }
+
+        let practical = cascades(SplitScheme::Practical(0.5)).split_distances(1.0, 81.0);
+        for i in 0..5 {
+            assert_relative_eq!((uniform[i] + logarithmic[i]) / 2.0, practical[i], epsilon = 1e-9);
+        }
+        assert_eq!(
+            uniform,
+            cascades(SplitScheme::Practical(0.0)).split_distances(1.0, 81.0)
+        );
+
+        let none = Cascades {
+            count: 0,
+            ..cascades(SplitScheme::Uniform)
+        };
+        assert!(none.split_distances(1.0, 81.0).is_empty());
+        assert!(none.compute(perspective(), Matrix4::identity()).unwrap().is_empty());
+        let two = Cascades {
+            count: 2,
+            ..cascades(SplitScheme::Uniform)
+        };
+        assert_eq!(vec![0.0, 40.5, 81.0], two.split_distances(0.0, 81.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "near distance of logarithmic splits must be positive")]
+    fn test_split_distances_logarithmic_zero_near() {
+        cascades(SplitScheme::Practical(0.5)).split_distances(0.0, 81.0);
+    }
+
+    #[test]
+    fn test_cascades() {
+        let view = Matrix4::look_at(
+            Point3::new(10.0, 5.0, 20.0),
+            Point3::new(-20.0, 0.0, -30.0),
+            Vector3::unit_y(),
+        );
+        let settings = cascades(SplitScheme::Practical(0.75));
+        let result = settings.compute(perspective(), view).unwrap();
+        assert_eq!(4, result.len());
+        assert_eq!(0.5, result[0].near);
+        assert_eq!(200.0, result[3].far);
+
+        for (i, cascade) in result.iter().enumerate() {
+            if i > 0 {
+                assert_eq!(result[i - 1].far, cascade.near);
+            }
+
+            let sphere = cascade.bounding_sphere;
+            for corner in cascade.corners.iter() {
+                let inside = (corner - sphere.center).magnitude() <= sphere.radius + 1e-9;
+                assert!(inside);
+                // the corners are visible to the light, and lie on the camera frustum
+                assert_ne!(Intersection::Outside, cascade.culler.test_point(*corner));
+                let depth = -(view.transform_point(Point3::from_vec(*corner))).z;
+                assert!(
+                    (depth - cascade.near).abs() < 1e-9 || (depth - cascade.far).abs() < 1e-9
+                );
+            }
+            // the far plane of the light touches the sphere
+            let shrunk = Sphere::from_params(sphere.center, sphere.radius - 1e-6);
+            assert_eq!(Intersection::Inside, cascade.culler.test_sphere(shrunk));
+
+            // casters between the light and the cascade are kept, casters behind it are not
+            let direction = settings.light_direction.normalize();
+            let caster = |distance: f64| {
+                let center = sphere.center + direction * distance;
+                BoundingBox::from_params(
+                    center - Vector3::new(0.1, 0.1, 0.1),
+                    center + Vector3::new(0.1, 0.1, 0.1),
+                )
+            };
+            let extent = sphere.radius + settings.caster_distance;
+            let culler = cascade.culler;
+            assert_eq!(Intersection::Inside, culler.test_bounding_box(caster(-extent + 1.0)));
+            assert_eq!(Intersection::Outside, culler.test_bounding_box(caster(-extent - 1.0)));
+            let behind = caster(sphere.radius + 1.0);
+            assert_eq!(Intersection::Outside, culler.test_bounding_box(behind));
+        }
+    }
+
+    #[test]
+    fn test_stable_cascades() {
+        let settings = cascades(SplitScheme::Logarithmic);
+        let eye = |t: f64| Point3::new(t * 0.37, 2.0, t * -0.21);
+        let view = |t: f64| {
+            let (sin, cos) = (t * 0.1).sin_cos();
+            Matrix4::look_at(eye(t), eye(t) + Vector3::new(sin, -0.2, cos), Vector3::unit_y())
+        };
+        let first = settings.compute(perspective(), view(0.0)).unwrap();
+
+        for i in 1..100 {
+            let t = f64::from(i);
+            let moved = settings.compute(perspective(), view(t)).unwrap();
+            for (a, b) in first.iter().zip(moved.iter()) {
+                // same size, and the light volumes only move in whole texels
+                assert_eq!(a.bounding_sphere.radius, b.bounding_sphere.radius);
+                let texel = 2.0 / 1024.0;
+                let offset = b.light_matrix * a.light_matrix.invert().unwrap();
+                for &translation in [offset.w.x, offset.w.y].iter() {
+                    let texels = translation / texel;
+                    assert_relative_eq!(texels.round(), texels, epsilon = 1e-3);
+                }
+            }
+        }
+    }
+}
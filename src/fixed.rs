@@ -0,0 +1,568 @@
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use cgmath::num_traits::Zero;
+use cgmath::{BaseFloat, Matrix4, Vector3};
+
+use float;
+use {BoundingBox, DepthRange, FrustumCuller, Plane, Scalar, Sphere};
+
+/// Number of fractional bits of a `Fixed`.
+pub const FRAC_BITS: u32 = 32;
+
+/// Signed fixed-point number with 32 integer and 32 fractional bits, stored in an `i64`.
+///
+/// The culling tests only use integer additions, subtractions, multiplications and comparisons on
+/// it, so they give the same results on every target. Operations saturate instead of overflowing,
+/// and the result of a multiplication or a division is rounded towards negative infinity.
+///
+/// Converting from floats is exact up to the rounding to the nearest fixed-point value, so the
+/// same float inputs always give the same fixed-point values.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Fixed(i64);
+
+impl Fixed {
+    /// Zero.
+    pub const ZERO: Fixed = Fixed(0);
+    /// One.
+    pub const ONE: Fixed = Fixed(1 << FRAC_BITS);
+    /// Smallest value, which negative infinities are converted to.
+    pub const MIN: Fixed = Fixed(i64::MIN);
+    /// Largest value, which positive infinities (such as the distance of a far plane at
+    /// infinity) are converted to.
+    pub const MAX: Fixed = Fixed(i64::MAX);
+
+    /// Creates a number from its raw representation, the value multiplied by `2^FRAC_BITS`.
+    #[inline]
+    pub fn from_bits(bits: i64) -> Self {
+        Fixed(bits)
+    }
+
+    /// Returns the raw representation of the number.
+    #[inline]
+    pub fn to_bits(self) -> i64 {
+        self.0
+    }
+
+    /// Converts a float to the nearest fixed-point value, saturating values out of range. NaN is
+    /// converted to zero.
+    pub fn from_float<S: BaseFloat>(value: S) -> Self {
+        let limit = S::from(i64::MAX).unwrap();
+        let scaled = float::round(value * S::from(1u64 << FRAC_BITS).unwrap());
+        if scaled.is_nan() {
+            Self::ZERO
+        } else if scaled >= limit {
+            Self::MAX
+        } else if scaled <= -limit {
+            Self::MIN
+        } else {
+            Fixed(scaled.to_i64().unwrap())
+        }
+    }
+
+    /// Converts the number to a float.
+    #[inline]
+    pub fn to_float<S: BaseFloat>(self) -> S {
+        S::from(self.0).unwrap() / S::from(1u64 << FRAC_BITS).unwrap()
+    }
+
+    /// Converts a vector of floats to fixed-point, component by component.
+    #[inline]
+    pub fn from_float_vector<S: BaseFloat>(v: Vector3<S>) -> Vector3<Fixed> {
+        Vector3::new(
+            Fixed::from_float(v.x),
+            Fixed::from_float(v.y),
+            Fixed::from_float(v.z),
+        )
+    }
+
+    /// Square root, rounded towards zero and computed with integer operations only. The square
+    /// root of a negative number is zero.
+    pub fn sqrt(self) -> Self {
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+
+        // the square root of `bits / 2^FRAC_BITS`, scaled by `2^FRAC_BITS`
+        let n = (self.0 as u128) << FRAC_BITS;
+        let mut rem = n;
+        let mut root = 0u128;
+        let mut bit = 1u128 << (126 - (n.leading_zeros() & !1));
+        while bit != 0 {
+            if rem >= root + bit {
+                rem -= root + bit;
+                root = (root >> 1) + bit;
+            } else {
+                root >>= 1;
+            }
+            bit >>= 2;
+        }
+        Fixed(root as i64)
+    }
+}
+
+impl From<i32> for Fixed {
+    #[inline]
+    fn from(value: i32) -> Self {
+        Fixed(i64::from(value) << FRAC_BITS)
+    }
+}
+
+impl fmt::Display for Fixed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_float::<f64>(), f)
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+
+    #[inline]
+    fn add(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_add(other.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+
+    #[inline]
+    fn sub(self, other: Fixed) -> Fixed {
+        Fixed(self.0.saturating_sub(other.0))
+    }
+}
+
+impl Mul for Fixed {
+    type Output = Fixed;
+
+    #[inline]
+    fn mul(self, other: Fixed) -> Fixed {
+        let product = (i128::from(self.0) * i128::from(other.0)) >> FRAC_BITS;
+        if product > i128::from(i64::MAX) {
+            Self::MAX
+        } else if product < i128::from(i64::MIN) {
+            Self::MIN
+        } else {
+            Fixed(product as i64)
+        }
+    }
+}
+
+impl Div for Fixed {
+    type Output = Fixed;
+
+    /// Divides two numbers. Dividing by zero gives `MAX` or `MIN`, depending on the sign of the
+    /// dividend.
+    #[inline]
+    fn div(self, other: Fixed) -> Fixed {
+        if other.0 == 0 {
+            return if self.0 >= 0 { Self::MAX } else { Self::MIN };
+        }
+
+        let dividend = i128::from(self.0) << FRAC_BITS;
+        let divisor = i128::from(other.0);
+        let mut quotient = dividend / divisor;
+        if dividend % divisor != 0 && (dividend < 0) != (divisor < 0) {
+            quotient -= 1;
+        }
+        if quotient > i128::from(i64::MAX) {
+            Self::MAX
+        } else if quotient < i128::from(i64::MIN) {
+            Self::MIN
+        } else {
+            Fixed(quotient as i64)
+        }
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+
+    #[inline]
+    fn neg(self) -> Fixed {
+        Fixed(self.0.saturating_neg())
+    }
+}
+
+impl Zero for Fixed {
+    #[inline]
+    fn zero() -> Self {
+        Self::ZERO
+    }
+
+    #[inline]
+    fn is_zero(&self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Scalar for Fixed {}
+
+impl FrustumCuller<Fixed> {
+    /// Converts the planes of a float frustum culler to fixed-point.
+    ///
+    /// A far plane at infinity is kept at infinity, so it's still never tested. Since the float
+    /// planes may differ between targets, use `from_fixed_matrix` when every peer has to start
+    /// from the same planes.
+    pub fn from_float<S: BaseFloat>(culler: &FrustumCuller<S>) -> Self {
+        let plane = |index| {
+            let plane = culler.plane(index);
+            Plane::from_params(
+                Fixed::from_float_vector(plane.normal),
+                Fixed::from_float(plane.distance),
+            )
+        };
+        Self::from_planes([plane(0), plane(1), plane(2), plane(3), plane(4), plane(5)])
+    }
+
+    /// Creates a fixed-point culler from a fixed-point matrix, like `from_matrix` does for floats.
+    ///
+    /// The planes are extracted and normalized with integer operations only, so the same matrix
+    /// gives the same planes on every target.
+    pub fn from_fixed_matrix(m: Matrix4<Fixed>) -> Self {
+        Self::from_fixed_matrix_with_depth_range(m, DepthRange::NegativeOneToOne)
+    }
+
+    /// Same as `from_fixed_matrix`, given the range of the depth coordinate in the clip space the
+    /// matrix maps to, like `from_matrix_with_depth_range`.
+    pub fn from_fixed_matrix_with_depth_range(m: Matrix4<Fixed>, depth_range: DepthRange) -> Self {
+        let x = [m.x.x, m.y.x, m.z.x, m.w.x];
+        let y = [m.x.y, m.y.y, m.z.y, m.w.y];
+        let z = [m.x.z, m.y.z, m.z.z, m.w.z];
+        let w = [m.x.w, m.y.w, m.z.w, m.w.w];
+
+        let add = |a: [Fixed; 4], b: [Fixed; 4]| {
+            [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]]
+        };
+        let sub = |a: [Fixed; 4], b: [Fixed; 4]| {
+            [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]]
+        };
+        let (near, far) = match depth_range {
+            DepthRange::NegativeOneToOne => (add(w, z), sub(w, z)),
+            DepthRange::ZeroToOne => (z, sub(w, z)),
+            DepthRange::OneToZero => (sub(w, z), z),
+        };
+
+        Self::from_planes([
+            normalize_plane(add(w, x)),
+            normalize_plane(sub(w, x)),
+            normalize_plane(add(w, y)),
+            normalize_plane(sub(w, y)),
+            normalize_plane(near),
+            normalize_plane(far),
+        ])
+    }
+}
+
+/// Fixed-point counterpart of the normalization of the planes extracted from a matrix. A plane
+/// with a zero normal lies at infinity, and it's given the largest or the smallest distance.
+fn normalize_plane([a, b, c, d]: [Fixed; 4]) -> Plane<Fixed> {
+    let normal = Vector3::new(a, b, c);
+    let length = (a * a + b * b + c * c).sqrt();
+    if length == Fixed::ZERO {
+        let distance = if d >= Fixed::ZERO {
+            Fixed::MAX
+        } else {
+            Fixed::MIN
+        };
+        return Plane::from_params(normal, distance);
+    }
+
+    Plane::from_params(
+        Vector3::new(a / length, b / length, c / length),
+        d / length,
+    )
+}
+
+impl BoundingBox<Fixed> {
+    /// Converts a float bounding box to fixed-point.
+    #[inline]
+    pub fn from_float<S: BaseFloat>(aab: BoundingBox<S>) -> Self {
+        Self::from_params(
+            Fixed::from_float_vector(aab.min),
+            Fixed::from_float_vector(aab.max),
+        )
+    }
+}
+
+impl Sphere<Fixed> {
+    /// Converts a float sphere to fixed-point.
+    #[inline]
+    pub fn from_float<S: BaseFloat>(sphere: Sphere<S>) -> Self {
+        Self::from_params(
+            Fixed::from_float_vector(sphere.center),
+            Fixed::from_float(sphere.radius),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use {BoundingBox, FrustumCuller, Intersection, Sphere};
+
+    use super::Fixed;
+
+    use DepthRange;
+
+    use cgmath::{Matrix4, Ortho, PerspectiveFov, Rad, Vector3, Vector4};
+
+    use std::f64;
+
+    /// Maximum error of a plane distance computed in fixed-point, for the coordinates used below.
+    const TOLERANCE: f64 = 1e-6;
+
+    /// Linear congruential generator, to get the same pseudo-random values on every run.
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, range: f64) -> f64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            ((self.0 >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0) * range
+        }
+
+        fn vector(&mut self, range: f64) -> Vector3<f64> {
+            Vector3::new(self.next(range), self.next(range), self.next(range))
+        }
+    }
+
+    fn cullers() -> Vec<FrustumCuller<f64>> {
+        vec![
+            FrustumCuller::from_perspective_fov(PerspectiveFov {
+                fovy: Rad(f64::consts::FRAC_PI_2),
+                aspect: 1.5,
+                near: 0.1,
+                far: 100.0,
+            }),
+            FrustumCuller::from_ortho(Ortho {
+                left: -1.0,
+                right: 3.0,
+                bottom: -2.0,
+                top: 1.0,
+                near: -1.0,
+                far: 10.0,
+            }),
+            FrustumCuller::from_matrix(
+                Matrix4::from(PerspectiveFov {
+                    fovy: Rad(1.0),
+                    aspect: 1.0,
+                    near: 0.5,
+                    far: 50.0,
+                }) * Matrix4::look_at(
+                    (3.0, 2.0, 1.0).into(),
+                    (0.0, 0.0, -10.0).into(),
+                    Vector3::unit_y(),
+                ),
+            ),
+            FrustumCuller::from_infinite_perspective_fov(Rad(1.2), 1.5, 0.1),
+        ]
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let half = Fixed::from_float(0.5);
+        assert_eq!(1 << 31, half.to_bits());
+        assert_eq!(Fixed::ONE, half + half);
+        assert_eq!(Fixed::from(-3), Fixed::from(3) * -Fixed::ONE);
+        assert_eq!(Fixed::from_float(0.25), half * half);
+        assert_eq!(-1.75, Fixed::from_float(-1.75).to_float::<f64>());
+        // products are rounded towards negative infinity
+        assert_eq!(Fixed::ZERO, Fixed::from_bits(1) * half);
+        assert_eq!(Fixed::from_bits(-1), Fixed::from_bits(-1) * half);
+
+        assert_eq!(Fixed::MAX, Fixed::from_float(f64::INFINITY));
+        assert_eq!(Fixed::MIN, Fixed::from_float(f64::NEG_INFINITY));
+        assert_eq!(Fixed::ZERO, Fixed::from_float(f64::NAN));
+        assert_eq!(Fixed::MAX, Fixed::MAX + Fixed::ONE);
+        assert_eq!(Fixed::MIN, Fixed::from(1 << 30) * -Fixed::from(1 << 30));
+        assert_eq!(Fixed::MAX, -Fixed::MIN);
+
+        assert_eq!(Fixed::from(3), Fixed::from(9).sqrt());
+        assert_eq!(Fixed::from_float(0.5), Fixed::from_float(0.25).sqrt());
+        assert_eq!(Fixed::ZERO, Fixed::from(-4).sqrt());
+        assert_eq!(Fixed::from_bits(1 << 16), Fixed::from_bits(1).sqrt());
+        assert_eq!(Fixed::from_bits(0xb504_f333_f9de), Fixed::MAX.sqrt());
+        assert_eq!(Fixed::from_float(-2.5), Fixed::from(5) / -Fixed::from(2));
+        assert_eq!(Fixed::from_bits(-1), Fixed::from_bits(-1) / Fixed::from(3));
+        assert_eq!(Fixed::from_bits(0x5555_5555), Fixed::ONE / Fixed::from(3));
+        assert_eq!(Fixed::MAX, Fixed::ONE / Fixed::ZERO);
+        assert_eq!(Fixed::MIN, -Fixed::ONE / Fixed::ZERO);
+        assert_eq!(Fixed::MAX, Fixed::from(1 << 30) / Fixed::from_float(0.25));
+    }
+
+    fn fixed_matrix(m: Matrix4<f64>) -> Matrix4<Fixed> {
+        let column = |c: Vector4<f64>| {
+            Vector4::new(
+                Fixed::from_float(c.x),
+                Fixed::from_float(c.y),
+                Fixed::from_float(c.z),
+                Fixed::from_float(c.w),
+            )
+        };
+        Matrix4 {
+            x: column(m.x),
+            y: column(m.y),
+            z: column(m.z),
+            w: column(m.w),
+        }
+    }
+
+    #[test]
+    fn test_from_fixed_matrix() {
+        let perspective = Matrix4::from(PerspectiveFov {
+            fovy: Rad(1.0),
+            aspect: 1.5,
+            near: 0.5,
+            far: 50.0,
+        });
+        let view = Matrix4::look_at(
+            (3.0, 2.0, 1.0).into(),
+            (0.0, 0.0, -10.0).into(),
+            Vector3::unit_y(),
+        );
+        let ortho = Matrix4::from(Ortho {
+            left: -1.0,
+            right: 3.0,
+            bottom: -2.0,
+            top: 1.0,
+            near: -1.0,
+            far: 10.0,
+        });
+        let infinite = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, -1.0, -1.0,
+            0.0, 0.0, -0.2, 0.0,
+        );
+        let reversed = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, -1.0,
+            0.0, 0.0, 0.1, 0.0,
+        );
+
+        for &(m, depth_range) in [
+            (perspective * view, DepthRange::NegativeOneToOne),
+            (ortho, DepthRange::NegativeOneToOne),
+            (infinite, DepthRange::NegativeOneToOne),
+            (reversed, DepthRange::OneToZero),
+        ].iter()
+        {
+            let expected = FrustumCuller::from_matrix_with_depth_range(m, depth_range);
+            let fixed =
+                FrustumCuller::from_fixed_matrix_with_depth_range(fixed_matrix(m), depth_range);
+            assert_eq!(expected.has_far_plane(), fixed.has_far_plane());
+            for i in 0..if expected.has_far_plane() { 6 } else { 5 } {
+                let (plane, expected) = (fixed.plane(i), expected.plane(i));
+                let normal = plane.normal.map(Fixed::to_float::<f64>);
+                let distance = plane.distance.to_float();
+                assert_relative_eq!(expected.normal, normal, epsilon = TOLERANCE);
+                assert_relative_eq!(expected.distance, distance, max_relative = TOLERANCE);
+            }
+        }
+
+        let c = FrustumCuller::from_fixed_matrix(fixed_matrix(perspective));
+        assert_eq!(c, FrustumCuller::from_fixed_matrix(fixed_matrix(perspective)));
+        assert_eq!(
+            Intersection::Inside,
+            c.test_point(Vector3::new(Fixed::ZERO, Fixed::ZERO, -Fixed::ONE))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_point(Vector3::new(Fixed::ZERO, Fixed::ZERO, Fixed::ONE))
+        );
+    }
+
+    #[test]
+    fn test_matches_f64() {
+        let mut rng = Lcg(7);
+        for c in cullers() {
+            let fixed = FrustumCuller::from_float(&c);
+            assert_eq!(c.has_far_plane(), fixed.has_far_plane());
+
+            let (mut tested, mut skipped) = (0, 0);
+            for _ in 0..10_000 {
+                let center = rng.vector(60.0);
+                let radius = rng.next(10.0).abs();
+                let sphere = Sphere::from_params(center, radius);
+
+                // results may only differ when the sphere is within the tolerance of a plane
+                let grown = c.test_sphere(Sphere::from_params(center, radius + TOLERANCE));
+                let shrunk = c.test_sphere(Sphere::from_params(center, radius - TOLERANCE));
+                let expected = c.test_sphere(sphere);
+                if grown == expected && shrunk == expected {
+                    assert_eq!(expected, fixed.test_sphere(Sphere::from_float(sphere)));
+                    tested += 1;
+                } else {
+                    skipped += 1;
+                }
+
+                let half = rng.vector(10.0).map(f64::abs);
+                let margin = Vector3::new(TOLERANCE, TOLERANCE, TOLERANCE);
+                let aab = BoundingBox::from_params(center - half, center + half);
+                let grown = c.test_bounding_box(BoundingBox::from_params(
+                    aab.min - margin,
+                    aab.max + margin,
+                ));
+                let shrunk = c.test_bounding_box(BoundingBox::from_params(
+                    aab.min + margin,
+                    aab.max - margin,
+                ));
+                let expected = c.test_bounding_box(aab);
+                if grown == expected && shrunk == expected {
+                    let aab = BoundingBox::from_float(aab);
+                    assert_eq!(expected, fixed.test_bounding_box(aab));
+                    assert_eq!(expected, fixed.test_bounding_box_masked(aab, 0x3f).0);
+                }
+            }
+            assert!(tested > 0 && skipped < tested / 100);
+        }
+    }
+
+    #[test]
+    fn test_points_match_f64() {
+        let mut rng = Lcg(11);
+        for c in cullers() {
+            let fixed = FrustumCuller::from_float(&c);
+            for _ in 0..10_000 {
+                let point = rng.vector(60.0);
+                let margin = c
+                    .planes()
+                    .iter()
+                    .take(if c.has_far_plane() { 6 } else { 5 })
+                    .map(|plane| plane.distance_to(point).abs())
+                    .fold(f64::INFINITY, f64::min);
+                if margin > TOLERANCE {
+                    assert_eq!(c.test_point(point), fixed.test_point(Fixed::from_float_vector(point)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_infinite_far_plane() {
+        let c = FrustumCuller::from_float(&FrustumCuller::from_infinite_perspective_fov(
+            Rad(f64::consts::FRAC_PI_2),
+            1.0,
+            0.1,
+        ));
+        assert!(!c.has_far_plane());
+        assert_eq!(
+            Intersection::Inside,
+            c.test_point(Vector3::new(Fixed::ZERO, Fixed::ZERO, Fixed::from(-1 << 30)))
+        );
+        assert_eq!(
+            Intersection::Inside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(-Fixed::ONE, -Fixed::ONE, Fixed::MIN),
+                Vector3::new(Fixed::ONE, Fixed::ONE, Fixed::from(-5)),
+            ))
+        );
+    }
+}
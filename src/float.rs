@@ -0,0 +1,28 @@
+// Floating point functions of the crate, which go through `libm` in `no_std` builds.
+//
+// In `no_std` builds, the values are computed in double precision, which gives correctly rounded
+// square roots for `f32` and `f64`.
+
+use cgmath::BaseFloat;
+
+macro_rules! float_fn {
+    ($name:ident, $libm:ident, $($arg:ident),+) => {
+        #[cfg(feature = "std")]
+        #[inline]
+        pub fn $name<S: BaseFloat>($($arg: S),+) -> S {
+            S::$name($($arg),+)
+        }
+
+        #[cfg(not(feature = "std"))]
+        #[inline]
+        pub fn $name<S: BaseFloat>($($arg: S),+) -> S {
+            S::from(::libm::$libm($($arg.to_f64().unwrap()),+)).unwrap()
+        }
+    };
+}
+
+float_fn!(sqrt, sqrt, x);
+float_fn!(floor, floor, x);
+float_fn!(round, round, x);
+float_fn!(powf, pow, x, y);
+float_fn!(tan, tan, x);
@@ -0,0 +1,515 @@
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+
+use cgmath::{BaseFloat, Vector3};
+
+use float;
+use {BoundingBox, FrustumCuller, Intersection};
+
+/// Integer coordinates of a grid cell.
+pub type Cell = (i32, i32, i32);
+
+/// Maximum number of cells an object is stored in. Bigger objects, as well as objects with
+/// non-finite bounds, are kept in a separate list and tested on their own.
+pub const MAX_OBJECT_CELLS: u64 = 4096;
+
+/// Handle to an object stored in a `Grid`.
+///
+/// Handles stay valid until the object is removed. After that, the handle may be given to a newly
+/// inserted object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Handle(usize);
+
+/// Sparse uniform grid of objects bounded by axis aligned bounding boxes, meant for large open
+/// worlds.
+///
+/// Each object is stored in every cell its bounding box overlaps, unless it overlaps more than
+/// `MAX_OBJECT_CELLS` of them. Only cells that hold objects are allocated.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Grid<S, T> {
+    origin: Vector3<S>,
+    cell_size: S,
+    #[cfg_attr(feature = "serde", serde(with = "cells"))]
+    cells: BTreeMap<Cell, Vec<usize>>,
+    /// objects that are too big to be stored in the cells they overlap
+    large: Vec<usize>,
+    entries: Vec<Option<(BoundingBox<S>, T)>>,
+    free: Vec<usize>,
+    len: usize,
+}
+
+impl<S: BaseFloat, T> Grid<S, T> {
+    /// Creates an empty grid of cubic cells of size `cell_size`. Cell `(0, 0, 0)` has its minimum
+    /// corner at `origin`.
+    pub fn new(origin: Vector3<S>, cell_size: S) -> Self {
+        Self {
+            origin,
+            cell_size,
+            cells: BTreeMap::new(),
+            large: Vec::new(),
+            entries: Vec::new(),
+            free: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of objects.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts an object and returns its handle.
+    pub fn insert<B>(&mut self, bounds: B, value: T) -> Handle
+    where
+        B: Into<BoundingBox<S>>,
+    {
+        let bounds = bounds.into();
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.entries[index] = Some((bounds, value));
+                index
+            }
+            None => {
+                self.entries.push(Some((bounds, value)));
+                self.entries.len() - 1
+            }
+        };
+
+        if self.is_large(&bounds) {
+            self.large.push(index);
+        } else {
+            let (min, max) = self.cell_range(&bounds);
+            for_each_cell(min, max, |cell| {
+                self.cells.entry(cell).or_default().push(index)
+            });
+        }
+        self.len += 1;
+        Handle(index)
+    }
+
+    /// Removes an object, returning its value, or `None` if the handle is not valid.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let (bounds, value) = self.entries.get_mut(handle.0).and_then(Option::take)?;
+
+        if self.is_large(&bounds) {
+            let position = self.large.iter().position(|&i| i == handle.0).unwrap();
+            self.large.swap_remove(position);
+        } else {
+            let (min, max) = self.cell_range(&bounds);
+            for_each_cell(min, max, |cell| {
+                let empty = {
+                    let objects = self.cells.get_mut(&cell).unwrap();
+                    let position = objects.iter().position(|&i| i == handle.0).unwrap();
+                    objects.swap_remove(position);
+                    objects.is_empty()
+                };
+                if empty {
+                    self.cells.remove(&cell);
+                }
+            });
+        }
+        self.free.push(handle.0);
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Returns the value of an object.
+    #[inline]
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.entries
+            .get(handle.0)
+            .and_then(Option::as_ref)
+            .map(|entry| &entry.1)
+    }
+
+    /// Returns a mutable reference to the value of an object.
+    #[inline]
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.entries
+            .get_mut(handle.0)
+            .and_then(Option::as_mut)
+            .map(|entry| &mut entry.1)
+    }
+
+    /// Returns the bounds of an object.
+    #[inline]
+    pub fn bounds(&self, handle: Handle) -> Option<BoundingBox<S>> {
+        self.entries
+            .get(handle.0)
+            .and_then(Option::as_ref)
+            .map(|entry| entry.0)
+    }
+
+    /// Returns the coordinates of the cell that contains the given point.
+    pub fn cell(&self, point: Vector3<S>) -> Cell {
+        (
+            self.coordinate(point.x - self.origin.x),
+            self.coordinate(point.y - self.origin.y),
+            self.coordinate(point.z - self.origin.z),
+        )
+    }
+
+    /// Returns the bounding box of a cell.
+    pub fn cell_bounds(&self, (x, y, z): Cell) -> BoundingBox<S> {
+        let min = self.origin + Vector3::new(
+            S::from(x).unwrap(),
+            S::from(y).unwrap(),
+            S::from(z).unwrap(),
+        ) * self.cell_size;
+        let size = Vector3::new(self.cell_size, self.cell_size, self.cell_size);
+        BoundingBox::from_params(min, min + size)
+    }
+
+    /// Appends the non-empty cells that are at least partially inside of the frustum to `visible`,
+    /// along with the result of testing them, in no particular order.
+    ///
+    /// Cells outside of the bounding box of the frustum are discarded without being tested. If
+    /// the frustum is unbounded (see `FrustumCuller::corners`) every cell is tested.
+    pub fn visible_cells(
+        &self,
+        culler: &FrustumCuller<S>,
+        visible: &mut Vec<(Cell, Intersection)>,
+    ) {
+        let range = culler.bounding_box().ok().map(|aab| self.cell_range(&aab));
+        let mut test = |cell| {
+            let intersection = culler.test_bounding_box(self.cell_bounds(cell));
+            if intersection != Intersection::Outside {
+                visible.push((cell, intersection));
+            }
+        };
+
+        match range {
+            // visit the cells in the range, or only the ones that exist if there are fewer
+            Some((min, max)) if volume(min, max) < self.cells.len() as u64 => {
+                for_each_cell(min, max, |cell| {
+                    if self.cells.contains_key(&cell) {
+                        test(cell);
+                    }
+                });
+            }
+            Some((min, max)) => {
+                for &cell in self.cells.keys() {
+                    if contains(min, max, cell) {
+                        test(cell);
+                    }
+                }
+            }
+            None => {
+                for &cell in self.cells.keys() {
+                    test(cell);
+                }
+            }
+        }
+    }
+
+    /// Appends the handles of the objects that are at least partially inside of the frustum to
+    /// `visible`, in increasing order and without duplicates.
+    ///
+    /// Objects are gathered from the cells returned by `visible_cells`. The ones in cells that are
+    /// fully inside of the frustum are accepted without being tested. Objects that overlap more
+    /// than `MAX_OBJECT_CELLS` cells are always tested.
+    pub fn cull(&self, culler: &FrustumCuller<S>, visible: &mut Vec<Handle>) {
+        let mut cells = Vec::new();
+        self.visible_cells(culler, &mut cells);
+
+        let start = visible.len();
+        for (cell, intersection) in cells {
+            for &index in self.cells[&cell].iter() {
+                if intersection == Intersection::Partial {
+                    let bounds = self.entries[index].as_ref().unwrap().0;
+                    if culler.test_bounding_box(bounds) == Intersection::Outside {
+                        continue;
+                    }
+                }
+                visible.push(Handle(index));
+            }
+        }
+        for &index in self.large.iter() {
+            let bounds = self.entries[index].as_ref().unwrap().0;
+            if culler.test_bounding_box(bounds) != Intersection::Outside {
+                visible.push(Handle(index));
+            }
+        }
+
+        // objects that span several cells may have been found more than once
+        visible[start..].sort_unstable();
+        let mut end = start;
+        for i in start..visible.len() {
+            if end == start || visible[end - 1] != visible[i] {
+                visible[end] = visible[i];
+                end += 1;
+            }
+        }
+        visible.truncate(end);
+    }
+
+    /// Returns true if an object with the given bounds is kept out of the cells, either because it
+    /// overlaps too many of them or because its bounds are not finite.
+    fn is_large(&self, bounds: &BoundingBox<S>) -> bool {
+        let finite = [bounds.min, bounds.max]
+            .iter()
+            .all(|v| v.x.is_finite() && v.y.is_finite() && v.z.is_finite());
+        let (min, max) = self.cell_range(bounds);
+        !finite || volume(min, max) > MAX_OBJECT_CELLS
+    }
+
+    /// Returns the range of cells covered by a bounding box.
+    fn cell_range(&self, bounds: &BoundingBox<S>) -> (Cell, Cell) {
+        (self.cell(bounds.min), self.cell(bounds.max))
+    }
+
+    /// Converts a coordinate relative to the origin of the grid to a cell coordinate, saturating
+    /// on overflow.
+    fn coordinate(&self, offset: S) -> i32 {
+        let cell = float::floor(offset / self.cell_size);
+        if cell >= S::from(i32::MAX).unwrap() {
+            i32::MAX
+        } else if cell <= S::from(i32::MIN).unwrap() {
+            i32::MIN
+        } else {
+            cell.to_i32().unwrap_or(0)
+        }
+    }
+}
+
+fn for_each_cell<F: FnMut(Cell)>(min: Cell, max: Cell, mut f: F) {
+    for x in min.0..=max.0 {
+        for y in min.1..=max.1 {
+            for z in min.2..=max.2 {
+                f((x, y, z));
+            }
+        }
+    }
+}
+
+#[inline]
+fn contains(min: Cell, max: Cell, cell: Cell) -> bool {
+    min.0 <= cell.0 && cell.0 <= max.0 && min.1 <= cell.1 && cell.1 <= max.1 && min.2 <= cell.2
+        && cell.2 <= max.2
+}
+
+/// Number of cells in a range, saturating on overflow.
+#[inline]
+fn volume(min: Cell, max: Cell) -> u64 {
+    let x = (i64::from(max.0) - i64::from(min.0) + 1).max(0) as u64;
+    let y = (i64::from(max.1) - i64::from(min.1) + 1).max(0) as u64;
+    let z = (i64::from(max.2) - i64::from(min.2) + 1).max(0) as u64;
+    x.saturating_mul(y).saturating_mul(z)
+}
+
+/// (De)serializes the cells of a grid as a sequence of pairs, since formats such as JSON only
+/// support string keys in maps.
+#[cfg(feature = "serde")]
+mod cells {
+    #[cfg(not(feature = "std"))]
+    use alloc::collections::BTreeMap;
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+    #[cfg(feature = "std")]
+    use std::collections::BTreeMap;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use super::Cell;
+
+    pub fn serialize<S>(cells: &BTreeMap<Cell, Vec<usize>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(cells.iter())
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<BTreeMap<Cell, Vec<usize>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let cells: Vec<(Cell, Vec<usize>)> = Deserialize::deserialize(deserializer)?;
+        Ok(cells.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use {BoundingBox, FrustumCuller, Intersection};
+
+    use super::{Grid, Handle};
+
+    use cgmath::{Matrix4, PerspectiveFov, Rad, Vector3, prelude::*};
+
+    use std::f64;
+
+    fn brute_force(
+        grid: &Grid<f64, ()>,
+        handles: &[Handle],
+        c: &FrustumCuller<f64>,
+        test: fn(&FrustumCuller<f64>, BoundingBox<f64>) -> Intersection,
+    ) -> Vec<Handle> {
+        handles
+            .iter()
+            .cloned()
+            .filter(|&h| test(c, grid.bounds(h).unwrap()) != Intersection::Outside)
+            .collect()
+    }
+
+    // the cells may reject some of the objects accepted by `test_bounding_box`, which is
+    // conservative, but never an object that actually intersects the frustum
+    fn assert_cull(grid: &Grid<f64, ()>, handles: &[Handle], c: &FrustumCuller<f64>) {
+        let mut visible = Vec::new();
+        grid.cull(c, &mut visible);
+
+        let conservative = brute_force(grid, handles, c, FrustumCuller::test_bounding_box);
+        let exact = brute_force(grid, handles, c, FrustumCuller::test_bounding_box_exact);
+        assert!(!exact.is_empty() && conservative.len() < handles.len());
+        assert!(visible.iter().all(|h| conservative.contains(h)));
+        assert!(exact.iter().all(|h| visible.contains(h)));
+    }
+
+    #[test]
+    fn test_cull_matches_brute_force() {
+        let mut grid = Grid::new(Vector3::new(-1.0, -1.0, -1.0), 8.0);
+
+        let mut handles = Vec::new();
+        for x in -60i32..60 {
+            for z in -60i32..60 {
+                let min = Vector3::new(x as f64 * 2.5, -1.0, z as f64 * 2.5);
+                let size = Vector3::new(1.0, 3.0, 1.0) * (1 + (x * z).abs() % 7) as f64;
+                handles.push(grid.insert(BoundingBox::from_params(min, min + size), ()));
+            }
+        }
+        for &handle in handles.iter().step_by(5) {
+            assert_eq!(Some(()), grid.remove(handle));
+        }
+        handles.retain(|&h| grid.get(h).is_some());
+        assert_eq!(handles.len(), grid.len());
+
+        let projection = PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.5,
+            near: 0.1,
+            far: 60.0,
+        };
+        let view = Matrix4::look_at(
+            (10.0, 5.0, 10.0).into(),
+            (-20.0, 0.0, -30.0).into(),
+            Vector3::unit_y(),
+        );
+        let c = FrustumCuller::from_matrix(Matrix4::from(projection) * view);
+
+        assert_cull(&grid, &handles, &c);
+
+        // every visible cell holds objects and overlaps the frustum, and the hidden ones are
+        // either culled by the cell range pass or by the frustum test
+        let mut cells = Vec::new();
+        grid.visible_cells(&c, &mut cells);
+        assert!(!cells.is_empty());
+        for &(cell, intersection) in cells.iter() {
+            assert!(grid.cells.contains_key(&cell));
+            assert_eq!(c.test_bounding_box(grid.cell_bounds(cell)), intersection);
+        }
+        let (min, max) = grid.cell_range(&c.bounding_box().unwrap());
+        let hidden = grid.cells.keys().filter(|cell| {
+            !cells.iter().any(|&(visible, _)| visible == **cell)
+        });
+        for &cell in hidden {
+            assert!(
+                !super::contains(min, max, cell)
+                    || c.test_bounding_box(grid.cell_bounds(cell)) == Intersection::Outside
+            );
+        }
+    }
+
+    #[test]
+    fn test_large_objects() {
+        let mut grid = Grid::new(Vector3::zero(), 1.0);
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        let small = Vector3::new(1.0, 1.0, 1.0);
+        let infinite = Vector3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let handles = vec![
+            grid.insert(BoundingBox::from_params(-small, small), ()),
+            grid.insert(BoundingBox::from_params(-small * 1e9, small * 1e9), ()),
+            grid.insert(BoundingBox::from_params(-infinite, infinite), ()),
+            grid.insert(BoundingBox::from_params(small * 1e9, infinite), ()),
+        ];
+        assert_eq!(3, grid.large.len());
+        assert_cull(&grid, &handles, &c);
+
+        assert_eq!(Some(()), grid.remove(handles[2]));
+        assert_eq!(Some(()), grid.remove(handles[0]));
+        let mut visible = Vec::new();
+        grid.cull(&c, &mut visible);
+        assert_eq!(vec![handles[1]], visible);
+    }
+
+    #[test]
+    fn test_cells() {
+        let grid: Grid<f64, ()> = Grid::new(Vector3::new(1.0, 2.0, 3.0), 2.0);
+        assert_eq!((0, 0, 0), grid.cell(Vector3::new(1.0, 2.0, 3.0)));
+        assert_eq!((-1, 1, 2), grid.cell(Vector3::new(0.5, 4.0, 8.0)));
+        assert_eq!(
+            (i32::MAX, i32::MIN, 0),
+            grid.cell(Vector3::new(1e300, -1e300, 3.5))
+        );
+        assert_eq!(
+            BoundingBox::from_params(Vector3::new(-1.0, 4.0, 7.0), Vector3::new(1.0, 6.0, 9.0)),
+            grid.cell_bounds((-1, 1, 2))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut grid = Grid::new(Vector3::new(-1.0, -1.0, -1.0), 4.0);
+        let mut handles = Vec::new();
+        for i in 0..20 {
+            let min = Vector3::new(i as f64 * 3.0 - 30.0, -2.0, -20.0 - i as f64);
+            let aab = BoundingBox::from_params(min, min + Vector3::new(2.0, 5.0, 1.0));
+            handles.push(grid.insert(aab, i));
+        }
+        grid.remove(handles[7]);
+
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+        let mut expected = Vec::new();
+        grid.cull(&c, &mut expected);
+        assert!(!expected.is_empty());
+
+        let json = ::serde_json::to_string(&grid).unwrap();
+        let binary = ::bincode::serialize(&grid).unwrap();
+        for grid in [
+            ::serde_json::from_str::<Grid<f64, i32>>(&json).unwrap(),
+            ::bincode::deserialize::<Grid<f64, i32>>(&binary).unwrap(),
+        ] {
+            assert_eq!(19, grid.len());
+            assert_eq!(None, grid.get(handles[7]));
+            assert_eq!(Some(&3), grid.get(handles[3]));
+            let mut visible = Vec::new();
+            grid.cull(&c, &mut visible);
+            assert_eq!(expected, visible);
+        }
+    }
+}
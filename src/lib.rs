@@ -1,10 +1,83 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "std"))]
+#[macro_use]
+extern crate alloc;
+#[cfg(all(not(feature = "std"), not(test)))]
+extern crate core as std;
+#[cfg(all(not(feature = "std"), test))]
+extern crate std;
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("either the `std` or the `libm` feature has to be enabled");
+
 extern crate cgmath;
+#[cfg(not(feature = "std"))]
+extern crate libm;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde_derive;
+
+#[cfg(test)]
+#[macro_use]
+extern crate approx;
+#[cfg(all(test, feature = "serde"))]
+extern crate bincode;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+
+pub use batch::{BoundingBoxes, Spheres};
+pub use bvh::Bvh;
+pub use cascades::{Cascade, Cascades, SplitScheme};
+pub use fixed::Fixed;
+pub use grid::Grid;
+pub use octree::Octree;
+#[cfg(feature = "simd")]
+pub use simd::SimdFrustumCuller;
 
+mod batch;
+pub mod bvh;
+pub mod cascades;
+pub mod fixed;
+mod float;
+pub mod grid;
+pub mod octree;
+#[cfg(feature = "simd")]
+mod simd;
+
+#[cfg(feature = "std")]
+use std::error;
+use std::fmt;
 use std::mem;
+use std::ops::{Mul, Neg, Sub};
+
+#[cfg(feature = "mint")]
+use cgmath::mint;
 
-use cgmath::{BaseFloat, Matrix4, Ortho, Perspective, PerspectiveFov, Vector3, prelude::*};
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use cgmath::{BaseFloat, Matrix3, Matrix4, Ortho, Perspective, PerspectiveFov, Quaternion, Rad,
+             Vector3, Vector4, num_traits::Zero, prelude::*};
+
+/// Scalar type of the planes of a `FrustumCuller` and of the volumes tested against them.
+///
+/// It only requires the arithmetic and comparisons needed by the culling tests, so that types
+/// other than floats, such as fixed-point numbers, can be used with them. Constructing a culler
+/// from a matrix and the geometric queries still require a `BaseFloat`.
+///
+/// It's implemented for every `BaseFloat`.
+pub trait Scalar:
+    Copy + PartialOrd + Zero + Sub<Output = Self> + Mul<Output = Self> + Neg<Output = Self>
+{
+}
+
+impl<S: BaseFloat> Scalar for S {}
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct FrustumCuller<S> {
     nx_x: S,
     nx_y: S,
@@ -34,6 +107,7 @@ pub struct FrustumCuller<S> {
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BoundingBox<S> {
     /// min point
     pub min: Vector3<S>,
@@ -43,6 +117,7 @@ pub struct BoundingBox<S> {
 
 #[repr(C)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Sphere<S> {
     /// min point
     pub center: Vector3<S>,
@@ -50,22 +125,226 @@ pub struct Sphere<S> {
     pub radius: S,
 }
 
-impl<S: BaseFloat> Sphere<S> {
+/// A plane given by the equation `normal.dot(p) + distance = 0`. Points for which the left hand
+/// side is positive lie on the inner side of the plane.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Plane<S> {
+    /// plane normal, pointing to the inner side of the plane
+    pub normal: Vector3<S>,
+    /// distance term of the plane equation
+    pub distance: S,
+}
+
+impl<S: Scalar> Plane<S> {
+    #[inline]
+    pub fn from_params(normal: Vector3<S>, distance: S) -> Self {
+        Self { normal, distance }
+    }
+
+    /// Returns the signed distance from the plane to the given point. The result is only a
+    /// euclidean distance if the plane is normalized.
+    #[inline]
+    pub fn distance_to(&self, point: Vector3<S>) -> S {
+        self.normal.x * point.x + self.normal.y * point.y + self.normal.z * point.z + self.distance
+    }
+}
+
+impl<S: BaseFloat> Plane<S> {
+    /// Returns the plane scaled so that its normal has unit length.
+    #[inline]
+    pub fn normalize(&self) -> Self {
+        let invl = float::sqrt(self.normal.magnitude2()).recip();
+        Self::from_params(self.normal * invl, self.distance * invl)
+    }
+}
+
+/// A box with an arbitrary orientation. Its local axes are the columns of `rotation`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrientedBoundingBox<S> {
+    /// center point
+    pub center: Vector3<S>,
+    /// half of the size of the box along each of its local axes
+    pub half_extents: Vector3<S>,
+    /// rotation from the local space of the box
+    pub rotation: Matrix3<S>,
+}
+
+impl<S: BaseFloat> OrientedBoundingBox<S> {
+    #[inline]
+    pub fn from_params(center: Vector3<S>, half_extents: Vector3<S>, rotation: Matrix3<S>) -> Self {
+        Self {
+            center,
+            half_extents,
+            rotation,
+        }
+    }
+
+    #[inline]
+    pub fn new() -> Self {
+        Self::from_params(Vector3::zero(), Vector3::zero(), Matrix3::identity())
+    }
+}
+
+impl<S: BaseFloat> Default for OrientedBoundingBox<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Scalar> Sphere<S> {
     #[inline]
     pub fn from_params(center: Vector3<S>, radius: S) -> Self {
         Self { center, radius }
     }
 
+    /// Same as `from_params`, with the center given as a `mint` vector. `from_params` keeps its
+    /// concrete `Vector3` parameter, because a generic `Into` bound would break type inference
+    /// for callers that pass `.into()` arguments.
+    #[cfg(feature = "mint")]
+    #[inline]
+    pub fn from_mint_params(center: mint::Vector3<S>, radius: S) -> Self {
+        Self::from_params(center.into(), radius)
+    }
+
     #[inline]
     pub fn new() -> Self {
         Self {
-            center: Vector3::zero(),
+            center: Vector3::new(S::zero(), S::zero(), S::zero()),
             radius: S::zero(),
         }
     }
 }
 
+/// A capsule, made of the points within `radius` of the segment that joins `start` and `end`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Capsule<S> {
+    /// first endpoint of the segment
+    pub start: Vector3<S>,
+    /// second endpoint of the segment
+    pub end: Vector3<S>,
+    /// radius
+    pub radius: S,
+}
+
+impl<S: Scalar> Capsule<S> {
+    #[inline]
+    pub fn from_params(start: Vector3<S>, end: Vector3<S>, radius: S) -> Self {
+        Self { start, end, radius }
+    }
+
+    #[inline]
+    pub fn new() -> Self {
+        let zero = Vector3::new(S::zero(), S::zero(), S::zero());
+        Self::from_params(zero, zero, S::zero())
+    }
+}
+
+impl<S: Scalar> Default for Capsule<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A cone with a flat base, such as the volume lit by a spot light. It encloses all the points
+/// within `range` of the apex and `angle` of the direction of the cone.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cone<S> {
+    /// apex point
+    pub apex: Vector3<S>,
+    /// direction of the axis of the cone, from the apex to the center of the base
+    pub direction: Vector3<S>,
+    /// angle between the axis and the sides of the cone, which must be less than a right angle
+    pub angle: Rad<S>,
+    /// distance from the apex to the base
+    pub range: S,
+}
+
+impl<S: BaseFloat> Cone<S> {
+    #[inline]
+    pub fn from_params(apex: Vector3<S>, direction: Vector3<S>, angle: Rad<S>, range: S) -> Self {
+        Self {
+            apex,
+            direction,
+            angle,
+            range,
+        }
+    }
+
+    /// Returns the center of the base of the cone.
+    #[inline]
+    pub fn base_center(&self) -> Vector3<S> {
+        self.apex + self.direction * (self.range / float::sqrt(self.direction.magnitude2()))
+    }
+
+    /// Returns the radius of the base of the cone.
+    #[inline]
+    pub fn base_radius(&self) -> S {
+        self.range * float::tan(self.angle.0)
+    }
+}
+
+/// A half-line that starts at `origin` and extends along `direction`. The points of the ray are
+/// `origin + direction * t` for non-negative values of `t`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Ray<S> {
+    /// origin point
+    pub origin: Vector3<S>,
+    /// direction, which doesn't need to be normalized
+    pub direction: Vector3<S>,
+}
+
+impl<S: BaseFloat> Ray<S> {
+    #[inline]
+    pub fn from_params(origin: Vector3<S>, direction: Vector3<S>) -> Self {
+        Self { origin, direction }
+    }
+
+    /// Returns the point of the ray for the parameter `t`.
+    #[inline]
+    pub fn at(&self, t: S) -> Vector3<S> {
+        self.origin + self.direction * t
+    }
+}
+
+/// A line segment joining `start` and `end`. The points of the segment are
+/// `start + (end - start) * t` for values of `t` between zero and one.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Segment<S> {
+    /// first endpoint
+    pub start: Vector3<S>,
+    /// second endpoint
+    pub end: Vector3<S>,
+}
+
+impl<S: BaseFloat> Segment<S> {
+    #[inline]
+    pub fn from_params(start: Vector3<S>, end: Vector3<S>) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the point of the segment for the parameter `t`.
+    #[inline]
+    pub fn at(&self, t: S) -> Vector3<S> {
+        self.start + (self.end - self.start) * t
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Intersection {
     /// fully inside the frustum
     Inside,
@@ -75,15 +354,119 @@ pub enum Intersection {
     Outside,
 }
 
-impl<S: BaseFloat> BoundingBox<S> {
+/// Range of the depth coordinate in clip space, which determines how the near and far planes are
+/// extracted from a projection matrix.
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DepthRange {
+    /// Depth goes from `-w` at the near plane to `w` at the far plane, as in OpenGL. This is the
+    /// convention of the projections built by `cgmath`.
+    NegativeOneToOne,
+    /// Depth goes from `0` at the near plane to `w` at the far plane, as in Vulkan, Direct3D,
+    /// Metal and wgpu.
+    ZeroToOne,
+    /// Depth goes from `w` at the near plane to `0` at the far plane (reversed-Z).
+    OneToZero,
+}
+
+impl Default for DepthRange {
+    #[inline]
+    fn default() -> Self {
+        DepthRange::NegativeOneToOne
+    }
+}
+
+/// Error returned when the geometry of a frustum can't be computed from its planes.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum FrustumError {
+    /// One of the planes has non-finite coefficients. This is the case of the far plane of a
+    /// projection with an infinite far plane, among others.
+    NonFinitePlane,
+    /// Three of the planes that meet at a corner of the frustum don't intersect in a single point
+    /// (two or more of them are parallel).
+    ParallelPlanes,
+    /// The matrix a frustum was built from has NaN or infinite entries.
+    NonFiniteMatrix,
+    /// One of the planes has a zero normal. Only the far plane may have one, when it lies at
+    /// infinity.
+    ZeroLengthNormal,
+    /// The planes don't enclose any volume, as is the case of an inverted frustum whose planes
+    /// face outwards, or of a far plane at infinity that rejects every point.
+    InvertedFrustum,
+}
+
+impl fmt::Display for FrustumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FrustumError::NonFinitePlane => write!(f, "frustum plane is not finite"),
+            FrustumError::ParallelPlanes => write!(f, "frustum planes don't meet at a point"),
+            FrustumError::NonFiniteMatrix => write!(f, "frustum matrix is not finite"),
+            FrustumError::ZeroLengthNormal => write!(f, "frustum plane has a zero normal"),
+            FrustumError::InvertedFrustum => write!(f, "frustum planes don't enclose a volume"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for FrustumError {}
+
+/// Bit of the plane mask that selects the `x = -1` (left) plane.
+pub const PLANE_MASK_NX: u8 = 1 << 0;
+/// Bit of the plane mask that selects the `x = 1` (right) plane.
+pub const PLANE_MASK_PX: u8 = 1 << 1;
+/// Bit of the plane mask that selects the `y = -1` (bottom) plane.
+pub const PLANE_MASK_NY: u8 = 1 << 2;
+/// Bit of the plane mask that selects the `y = 1` (top) plane.
+pub const PLANE_MASK_PY: u8 = 1 << 3;
+/// Bit of the plane mask that selects the `z = -1` (near) plane.
+pub const PLANE_MASK_NZ: u8 = 1 << 4;
+/// Bit of the plane mask that selects the `z = 1` (far) plane.
+pub const PLANE_MASK_PZ: u8 = 1 << 5;
+/// Plane mask that selects all six frustum planes.
+pub const PLANE_MASK_ALL: u8 = PLANE_MASK_NX | PLANE_MASK_PX | PLANE_MASK_NY | PLANE_MASK_PY
+    | PLANE_MASK_NZ | PLANE_MASK_PZ;
+
+impl<S: Scalar> Default for Sphere<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: Scalar> BoundingBox<S> {
     #[inline]
     pub fn from_params(min: Vector3<S>, max: Vector3<S>) -> Self {
         Self { min, max }
     }
 
+    /// Same as `from_params`, with the corners given as `mint` vectors. `from_params` keeps its
+    /// concrete `Vector3` parameters, because a generic `Into` bound would break type inference
+    /// for callers that pass `.into()` arguments.
+    #[cfg(feature = "mint")]
+    #[inline]
+    pub fn from_mint_params(min: mint::Vector3<S>, max: mint::Vector3<S>) -> Self {
+        Self::from_params(min.into(), max.into())
+    }
+
     #[inline]
     pub fn new() -> Self {
-        Self::from_params(Vector3::zero(), Vector3::zero())
+        let zero = Vector3::new(S::zero(), S::zero(), S::zero());
+        Self::from_params(zero, zero)
+    }
+}
+
+impl<S: Scalar> Default for BoundingBox<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: BaseFloat> Default for FrustumCuller<S> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -111,97 +494,190 @@ impl<S: BaseFloat> FrustumCuller<S> {
         Self::from_matrix(ortho.into())
     }
 
+    /// Creates a `FrustumCuller` from a perspective projection whose far plane lies at infinity,
+    /// so that nothing is culled for being too far away.
+    ///
+    /// The projection follows the OpenGL convention, like the one of `from_perspective_fov`.
+    ///
+    /// The distance of the far plane is infinite, which some serialization formats, such as JSON,
+    /// can't represent.
+    pub fn from_infinite_perspective_fov(fovy: Rad<S>, aspect: S, near: S) -> Self {
+        let two = S::one() + S::one();
+        let f = float::tan(fovy.0 / two).recip();
+
+        let m = Matrix4::from_cols(
+            Vector4::new(f / aspect, S::zero(), S::zero(), S::zero()),
+            Vector4::new(S::zero(), f, S::zero(), S::zero()),
+            Vector4::new(S::zero(), S::zero(), -S::one(), -S::one()),
+            Vector4::new(S::zero(), S::zero(), -two * near, S::zero()),
+        );
+        Self::from_matrix(m)
+    }
+
+    /// Creates a `FrustumCuller` from a reversed-Z projection matrix, which maps the near plane to
+    /// a depth of one and the far plane to a depth of zero. The far plane may lie at infinity.
+    pub fn from_reversed_z(m: Matrix4<S>) -> Self {
+        Self::from_matrix_with_depth_range(m, DepthRange::OneToZero)
+    }
+
     /// Creates a `FrustumCuller` from an arbitrary matrix, from which the frustum planes are
-    /// computed.
+    /// computed. The matrix is expected to map the frustum to OpenGL clip space, where the depth
+    /// ranges from `-w` to `w` (see `from_matrix_with_depth_range` for other conventions).
     pub fn from_matrix(m: Matrix4<S>) -> Self {
-        let mut culler: Self = unsafe { mem::zeroed() };
-
-        culler.nx_x = m.x.w + m.x.x;
-        culler.nx_y = m.y.w + m.y.x;
-        culler.nx_z = m.z.w + m.z.x;
-        culler.nx_w = m.w.w + m.w.x;
-        //if (allow_test_spheres) {
-        let invl = (culler.nx_x * culler.nx_x + culler.nx_y * culler.nx_y
-            + culler.nx_z * culler.nx_z)
-            .sqrt()
-            .recip();
-        culler.nx_x *= invl;
-        culler.nx_y *= invl;
-        culler.nx_z *= invl;
-        culler.nx_w *= invl;
-        //}
-        culler.px_x = m.x.w - m.x.x;
-        culler.px_y = m.y.w - m.y.x;
-        culler.px_z = m.z.w - m.z.x;
-        culler.px_w = m.w.w - m.w.x;
-        //if (allow_test_spheres) {
-        let invl = (culler.px_x * culler.px_x + culler.px_y * culler.px_y
-            + culler.px_z * culler.px_z)
-            .sqrt()
-            .recip();
-        culler.px_x *= invl;
-        culler.px_y *= invl;
-        culler.px_z *= invl;
-        culler.px_w *= invl;
-        //}
-        culler.ny_x = m.x.w + m.x.y;
-        culler.ny_y = m.y.w + m.y.y;
-        culler.ny_z = m.z.w + m.z.y;
-        culler.ny_w = m.w.w + m.w.y;
-        //if (allow_test_spheres) {
-        let invl = (culler.ny_x * culler.ny_x + culler.ny_y * culler.ny_y
-            + culler.ny_z * culler.ny_z)
-            .sqrt()
-            .recip();
-        culler.ny_x *= invl;
-        culler.ny_y *= invl;
-        culler.ny_z *= invl;
-        culler.ny_w *= invl;
-        //}
-        culler.py_x = m.x.w - m.x.y;
-        culler.py_y = m.y.w - m.y.y;
-        culler.py_z = m.z.w - m.z.y;
-        culler.py_w = m.w.w - m.w.y;
-        //if (allow_test_spheres) {
-        let invl = (culler.py_x * culler.py_x + culler.py_y * culler.py_y
-            + culler.py_z * culler.py_z)
-            .sqrt()
-            .recip();
-        culler.py_x *= invl;
-        culler.py_y *= invl;
-        culler.py_z *= invl;
-        culler.py_w *= invl;
-        //}
-        culler.nz_x = m.x.w + m.x.z;
-        culler.nz_y = m.y.w + m.y.z;
-        culler.nz_z = m.z.w + m.z.z;
-        culler.nz_w = m.w.w + m.w.z;
-        //if (allow_test_spheres) {
-        let invl = (culler.nz_x * culler.nz_x + culler.nz_y * culler.nz_y
-            + culler.nz_z * culler.nz_z)
-            .sqrt()
-            .recip();
-        culler.nz_x *= invl;
-        culler.nz_y *= invl;
-        culler.nz_z *= invl;
-        culler.nz_w *= invl;
-        //}
-        culler.pz_x = m.x.w - m.x.z;
-        culler.pz_y = m.y.w - m.y.z;
-        culler.pz_z = m.z.w - m.z.z;
-        culler.pz_w = m.w.w - m.w.z;
-        //if (allow_test_spheres) {
-        let invl = (culler.pz_x * culler.pz_x + culler.pz_y * culler.pz_y
-            + culler.pz_z * culler.pz_z)
-            .sqrt()
-            .recip();
-        culler.pz_x *= invl;
-        culler.pz_y *= invl;
-        culler.pz_z *= invl;
-        culler.pz_w *= invl;
-        //}
-
-        culler
+        Self::from_matrix_with_depth_range(m, DepthRange::NegativeOneToOne)
+    }
+
+    /// Same as `from_matrix`, with the matrix given as a column major `mint` matrix.
+    /// `from_matrix` keeps its concrete `Matrix4` parameter, because a generic `Into` bound would
+    /// break type inference for callers that pass `.into()` arguments.
+    #[cfg(feature = "mint")]
+    pub fn from_mint_matrix(m: mint::ColumnMatrix4<S>) -> Self {
+        Self::from_matrix(m.into())
+    }
+
+    /// Creates a `FrustumCuller` from an arbitrary matrix, given the range of the depth coordinate
+    /// in the clip space the matrix maps to.
+    ///
+    /// A plane at infinity, such as the far plane of a projection with an infinite far plane,
+    /// doesn't cull anything.
+    pub fn from_matrix_with_depth_range(m: Matrix4<S>, depth_range: DepthRange) -> Self {
+        let x = Vector4::new(m.x.x, m.y.x, m.z.x, m.w.x);
+        let y = Vector4::new(m.x.y, m.y.y, m.z.y, m.w.y);
+        let z = Vector4::new(m.x.z, m.y.z, m.z.z, m.w.z);
+        let w = Vector4::new(m.x.w, m.y.w, m.z.w, m.w.w);
+
+        let (near, far) = match depth_range {
+            DepthRange::NegativeOneToOne => (w + z, w - z),
+            DepthRange::ZeroToOne => (z, w - z),
+            DepthRange::OneToZero => (w - z, z),
+        };
+
+        Self::from_planes([
+            normalize_plane(w + x),
+            normalize_plane(w - x),
+            normalize_plane(w + y),
+            normalize_plane(w - y),
+            normalize_plane(near),
+            normalize_plane(far),
+        ])
+    }
+
+    /// Same as `from_matrix`, but fails instead of returning a culler with meaningless planes when
+    /// the matrix is degenerate.
+    ///
+    /// Returns an error if the matrix has NaN or infinite entries, or if the resulting culler is
+    /// not valid (see `is_valid`).
+    pub fn try_from_matrix(m: Matrix4<S>) -> Result<Self, FrustumError> {
+        Self::try_from_matrix_with_depth_range(m, DepthRange::NegativeOneToOne)
+    }
+
+    /// Same as `from_matrix_with_depth_range`, but fails under the same conditions as
+    /// `try_from_matrix`.
+    pub fn try_from_matrix_with_depth_range(
+        m: Matrix4<S>,
+        depth_range: DepthRange,
+    ) -> Result<Self, FrustumError> {
+        let finite = AsRef::<[S; 16]>::as_ref(&m).iter().all(|e| e.is_finite());
+        if !finite {
+            return Err(FrustumError::NonFiniteMatrix);
+        }
+
+        let culler = Self::from_matrix_with_depth_range(m, depth_range);
+        culler.validate()?;
+        Ok(culler)
+    }
+}
+
+impl<S: Scalar> FrustumCuller<S> {
+    /// Creates a frustum culler from six arbitrary planes, given in the same order as the one
+    /// returned by the `planes` method. Normals must point towards the inside of the frustum.
+    ///
+    /// The planes are used as given, so they should be normalized if the culler is going to be
+    /// used to test spheres.
+    pub fn from_planes(planes: [Plane<S>; 6]) -> Self {
+        let [nx, px, ny, py, nz, pz] = planes;
+        Self {
+            nx_x: nx.normal.x,
+            nx_y: nx.normal.y,
+            nx_z: nx.normal.z,
+            nx_w: nx.distance,
+            px_x: px.normal.x,
+            px_y: px.normal.y,
+            px_z: px.normal.z,
+            px_w: px.distance,
+            ny_x: ny.normal.x,
+            ny_y: ny.normal.y,
+            ny_z: ny.normal.z,
+            ny_w: ny.distance,
+            py_x: py.normal.x,
+            py_y: py.normal.y,
+            py_z: py.normal.z,
+            py_w: py.distance,
+            nz_x: nz.normal.x,
+            nz_y: nz.normal.y,
+            nz_z: nz.normal.z,
+            nz_w: nz.distance,
+            pz_x: pz.normal.x,
+            pz_y: pz.normal.y,
+            pz_z: pz.normal.z,
+            pz_w: pz.distance,
+        }
+    }
+
+    /// Returns the six planes of the frustum, with their normals pointing inwards, in the
+    /// following order: left, right, bottom, top, near and far. This is the same order used by the
+    /// `PLANE_MASK_*` bits.
+    ///
+    /// A far plane at infinity is reported with a zero normal and an infinite distance.
+    pub fn planes(&self) -> [Plane<S>; 6] {
+        [
+            self.plane(0),
+            self.plane(1),
+            self.plane(2),
+            self.plane(3),
+            self.plane(4),
+            self.plane(5),
+        ]
+    }
+
+    /// Returns the plane with the given index (see `planes` for the order).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than 5.
+    #[inline]
+    pub fn plane(&self, index: usize) -> Plane<S> {
+        let (a, b, c, d) = self.plane_coefficients(index);
+        Plane::from_params(Vector3::new(a, b, c), d)
+    }
+
+    /// Returns false if the far plane lies at infinity, as in the frustums created with
+    /// `from_infinite_perspective_fov`. In that case the far plane is never tested.
+    ///
+    /// A plane at infinity has a zero normal and a distance that accepts every point.
+    #[inline]
+    pub fn has_far_plane(&self) -> bool {
+        let at_infinity = self.pz_x == S::zero() && self.pz_y == S::zero()
+            && self.pz_z == S::zero() && self.pz_w >= S::zero();
+        !at_infinity
+    }
+
+    /// Returns the mask of the planes that have to be tested, that is, all of them but the far
+    /// plane when it lies at infinity.
+    #[inline]
+    pub(crate) fn plane_mask(&self) -> u8 {
+        if self.has_far_plane() {
+            PLANE_MASK_ALL
+        } else {
+            PLANE_MASK_ALL & !PLANE_MASK_PZ
+        }
+    }
+
+    /// Same as `test_point`, with the point given as a `mint` vector.
+    #[cfg(feature = "mint")]
+    #[inline]
+    pub fn test_mint_point(&self, point: mint::Vector3<S>) -> Intersection {
+        self.test_point(point.into())
     }
 
     /// Test wether a 3D point lies inside of the frustum
@@ -215,8 +691,9 @@ impl<S: BaseFloat> FrustumCuller<S> {
                 >= S::zero()
             && self.nz_x * point.x + self.nz_y * point.y + self.nz_z * point.z + self.nz_w
                 >= S::zero()
-            && self.pz_x * point.x + self.pz_y * point.y + self.pz_z * point.z + self.pz_w
-                >= S::zero()
+            && (!self.has_far_plane()
+                || self.pz_x * point.x + self.pz_y * point.y + self.pz_z * point.z + self.pz_w
+                    >= S::zero())
         {
             Intersection::Inside
         } else {
@@ -257,6 +734,13 @@ impl<S: BaseFloat> FrustumCuller<S> {
                             + self.nz_w;
                         if dist >= -sphere.radius {
                             inside &= dist >= sphere.radius;
+                            if !self.has_far_plane() {
+                                return if inside {
+                                    Intersection::Inside
+                                } else {
+                                    Intersection::Partial
+                                };
+                            }
                             dist = self.pz_x * sphere.center.x + self.pz_y * sphere.center.y
                                 + self.pz_z * sphere.center.z
                                 + self.pz_w;
@@ -423,6 +907,13 @@ impl<S: BaseFloat> FrustumCuller<S> {
                                 } else {
                                     aab.min.z
                                 } >= -self.nz_w;
+                            if !self.has_far_plane() {
+                                return if inside {
+                                    Intersection::Inside
+                                } else {
+                                    Intersection::Partial
+                                };
+                            }
                             if self.pz_x * if self.pz_x < S::zero() {
                                 aab.min.x
                             } else {
@@ -468,224 +959,2618 @@ impl<S: BaseFloat> FrustumCuller<S> {
 
         Intersection::Outside
     }
-}
 
-impl<S> From<(Vector3<S>, Vector3<S>)> for BoundingBox<S> {
-    #[inline]
-    fn from((min, max): (Vector3<S>, Vector3<S>)) -> Self {
-        Self { min, max }
-    }
-}
+    /// Same as `test_sphere`, but only the planes selected by `mask` (a combination of the
+    /// `PLANE_MASK_*` bits) are tested.
+    ///
+    /// Along with the intersection, it returns the mask of the tested planes that the sphere is
+    /// still straddling. When walking a hierarchy, pass that mask down to the children of a
+    /// `Partial` node so they skip the planes their parent is already fully inside of. If the
+    /// result is `Outside`, the given mask is returned unchanged.
+    pub fn test_sphere_masked<T>(&self, sphere: T, mask: u8) -> (Intersection, u8)
+    where
+        T: Into<Sphere<S>>,
+    {
+        let sphere = sphere.into();
 
-impl<S> From<(Vector3<S>, S)> for Sphere<S> {
-    #[inline]
-    fn from((center, radius): (Vector3<S>, S)) -> Self {
-        Self { center, radius }
-    }
-}
+        let tested = mask & self.plane_mask();
+        let mut straddled = 0;
+        for i in 0..6 {
+            let bit = 1 << i;
+            if tested & bit == 0 {
+                continue;
+            }
 
-#[cfg(test)]
-mod tests {
-    use {BoundingBox, FrustumCuller, Intersection, Sphere};
+            let (a, b, c, d) = self.plane_coefficients(i);
+            let dist = a * sphere.center.x + b * sphere.center.y + c * sphere.center.z + d;
+            let visible = dist >= -sphere.radius;
+            if !visible {
+                return (Intersection::Outside, mask);
+            }
+            let inside = dist >= sphere.radius;
+            if !inside {
+                straddled |= bit;
+            }
+        }
 
-    use cgmath::{Matrix4, Ortho, PerspectiveFov, Rad, Vector3, prelude::*};
+        if straddled == 0 {
+            (Intersection::Inside, straddled)
+        } else {
+            (Intersection::Partial, straddled)
+        }
+    }
 
-    #[test]
-    fn sphere_in_frustum_ortho() {
-        let frustum_culling = FrustumCuller::from_matrix(
-            Ortho {
-                left: -1.0,
-                right: 1.0,
-                bottom: -1.0,
-                top: 1.0,
-                near: -1.0,
-                far: 1.0,
-            }.into(),
-        );
+    /// Same as `test_bounding_box`, but only the planes selected by `mask` (a combination of the
+    /// `PLANE_MASK_*` bits) are tested.
+    ///
+    /// Along with the intersection, it returns the mask of the tested planes that the box is still
+    /// straddling. When walking a hierarchy, pass that mask down to the children of a `Partial`
+    /// node so they skip the planes their parent is already fully inside of. If the result is
+    /// `Outside`, the given mask is returned unchanged.
+    pub fn test_bounding_box_masked<T>(&self, aab: T, mask: u8) -> (Intersection, u8)
+    where
+        T: Into<BoundingBox<S>>,
+    {
+        let aab = aab.into();
 
-        assert_eq!(
-            Intersection::Inside,
-            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(0.0, 0.0, 0.0), 0.1))
-        );
-        assert_eq!(
-            Intersection::Partial,
-            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(1.0, 0.0, 0.0), 0.1))
-        );
-        assert_eq!(
-            Intersection::Outside,
-            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(1.2, 0.0, 0.0), 0.1))
-        );
-    }
+        let tested = mask & self.plane_mask();
+        let mut straddled = 0;
+        for i in 0..6 {
+            let bit = 1 << i;
+            if tested & bit == 0 {
+                continue;
+            }
 
-    #[test]
-    fn sphere_in_frustum_perspective() {
-        let frustum_culling = FrustumCuller::from_matrix(
+            let (a, b, c, d) = self.plane_coefficients(i);
+            let (px, nx) = if a < S::zero() {
+                (aab.min.x, aab.max.x)
+            } else {
+                (aab.max.x, aab.min.x)
+            };
+            let (py, ny) = if b < S::zero() {
+                (aab.min.y, aab.max.y)
+            } else {
+                (aab.max.y, aab.min.y)
+            };
+            let (pz, nz) = if c < S::zero() {
+                (aab.min.z, aab.max.z)
+            } else {
+                (aab.max.z, aab.min.z)
+            };
+
+            let visible = a * px + b * py + c * pz >= -d;
+            if !visible {
+                return (Intersection::Outside, mask);
+            }
+            let inside = a * nx + b * ny + c * nz >= -d;
+            if !inside {
+                straddled |= bit;
+            }
+        }
+
+        if straddled == 0 {
+            (Intersection::Inside, straddled)
+        } else {
+            (Intersection::Partial, straddled)
+        }
+    }
+
+    /// Tests the volume swept by a sphere that moves by `displacement`, that is, the union of all
+    /// the positions of the sphere along its path. Partial and full intersections are told apart.
+    ///
+    /// This is meant for objects rendered with motion blur, which cover their whole path in a
+    /// single frame. With no displacement, the result is the same as the one of `test_sphere`.
+    pub fn test_swept_sphere<T>(&self, sphere: T, displacement: Vector3<S>) -> Intersection
+    where
+        T: Into<Sphere<S>>,
+    {
+        let sphere = sphere.into();
+
+        self.test_planes(|a, b, c, d| {
+            let start = a * sphere.center.x + b * sphere.center.y + c * sphere.center.z + d;
+            let end = start + (a * displacement.x + b * displacement.y + c * displacement.z);
+            let (near, far) = if end < start { (end, start) } else { (start, end) };
+            (far >= -sphere.radius, near >= sphere.radius)
+        })
+    }
+
+    /// Tests the volume swept by an axis aligned bounding box that moves by `displacement`. Like
+    /// `test_swept_sphere`, it's meant for objects rendered with motion blur.
+    ///
+    /// With no displacement, the result is the same as the one of `test_bounding_box`.
+    pub fn test_swept_box<T>(&self, aab: T, displacement: Vector3<S>) -> Intersection
+    where
+        T: Into<BoundingBox<S>>,
+    {
+        let aab = aab.into();
+
+        self.test_planes(|a, b, c, d| {
+            let (px, nx) = if a < S::zero() {
+                (aab.min.x, aab.max.x)
+            } else {
+                (aab.max.x, aab.min.x)
+            };
+            let (py, ny) = if b < S::zero() {
+                (aab.min.y, aab.max.y)
+            } else {
+                (aab.max.y, aab.min.y)
+            };
+            let (pz, nz) = if c < S::zero() {
+                (aab.min.z, aab.max.z)
+            } else {
+                (aab.max.z, aab.min.z)
+            };
+
+            // the box moves towards the inner side of the plane if the shift is positive
+            let shift = a * displacement.x + b * displacement.y + c * displacement.z;
+            let (forward, backward) = if shift < S::zero() {
+                (S::zero(), shift)
+            } else {
+                (shift, S::zero())
+            };
+            (
+                a * px + b * py + c * pz + forward >= -d,
+                a * nx + b * ny + c * nz + backward >= -d,
+            )
+        })
+    }
+
+    /// Tests wether a capsule intersects with the frustum, telling partial and full intersections
+    /// apart.
+    ///
+    /// Like in the case of spheres, each plane is tested exactly, but a capsule that is outside of
+    /// the frustum near one of its corners may still be reported as `Partial`.
+    pub fn test_capsule<T>(&self, capsule: T) -> Intersection
+    where
+        T: Into<Capsule<S>>,
+    {
+        let capsule = capsule.into();
+
+        self.test_planes(|a, b, c, d| {
+            let start = a * capsule.start.x + b * capsule.start.y + c * capsule.start.z + d;
+            let end = a * capsule.end.x + b * capsule.end.y + c * capsule.end.z + d;
+            let (near, far) = if end < start { (end, start) } else { (start, end) };
+            (far >= -capsule.radius, near >= capsule.radius)
+        })
+    }
+
+    /// Tests wether a triangle intersects with the frustum, telling partial and full intersections
+    /// apart.
+    ///
+    /// Like in the case of boxes, a triangle that is outside of the frustum near one of its
+    /// corners may still be reported as `Partial`. Use `clip_triangle` to get the exact visible
+    /// part.
+    pub fn test_triangle(&self, a: Vector3<S>, b: Vector3<S>, c: Vector3<S>) -> Intersection {
+        let vertices = [a, b, c];
+
+        self.test_planes(|a, b, c, d| {
+            let mut visible = false;
+            let mut inside = true;
+            for v in vertices.iter() {
+                let dist = a * v.x + b * v.y + c * v.z + d;
+                visible |= dist >= S::zero();
+                inside &= dist >= S::zero();
+            }
+            (visible, inside)
+        })
+    }
+
+    /// Tests a convex volume against the planes of the frustum. For each plane `(a, b, c, d)`,
+    /// `test` returns whether the volume is at least partially on its inner side, and whether it's
+    /// fully on it.
+    fn test_planes<F>(&self, test: F) -> Intersection
+    where
+        F: Fn(S, S, S, S) -> (bool, bool),
+    {
+        let tested = self.plane_mask();
+        let mut inside = true;
+        for i in 0..6 {
+            if tested & (1 << i) == 0 {
+                continue;
+            }
+
+            let (a, b, c, d) = self.plane_coefficients(i);
+            let (visible, fully_inside) = test(a, b, c, d);
+            if !visible {
+                return Intersection::Outside;
+            }
+            inside &= fully_inside;
+        }
+
+        if inside {
+            Intersection::Inside
+        } else {
+            Intersection::Partial
+        }
+    }
+
+    /// Returns the coefficients `(a, b, c, d)` of the plane with the given index, in the same
+    /// order as the `PLANE_MASK_*` bits.
+    #[inline]
+    fn plane_coefficients(&self, index: usize) -> (S, S, S, S) {
+        match index {
+            0 => (self.nx_x, self.nx_y, self.nx_z, self.nx_w),
+            1 => (self.px_x, self.px_y, self.px_z, self.px_w),
+            2 => (self.ny_x, self.ny_y, self.ny_z, self.ny_w),
+            3 => (self.py_x, self.py_y, self.py_z, self.py_w),
+            4 => (self.nz_x, self.nz_y, self.nz_z, self.nz_w),
+            5 => (self.pz_x, self.pz_y, self.pz_z, self.pz_w),
+            _ => panic!("plane index out of bounds: {}", index),
+        }
+    }
+}
+
+impl<S: BaseFloat> FrustumCuller<S> {
+    /// Tests wether a cone intersects with the frustum, telling partial and full intersections
+    /// apart. The direction of the cone doesn't need to be normalized.
+    ///
+    /// Each plane is tested exactly against the apex and the base of the cone.
+    pub fn test_cone<T>(&self, cone: T) -> Intersection
+    where
+        T: Into<Cone<S>>,
+    {
+        let cone = cone.into();
+        let axis = cone.direction / float::sqrt(cone.direction.magnitude2());
+        let base = cone.apex + axis * cone.range;
+        let radius = cone.base_radius();
+
+        self.test_planes(|a, b, c, d| {
+            let normal = Vector3::new(a, b, c);
+            let apex = normal.dot(cone.apex) + d;
+            let center = normal.dot(base) + d;
+            // extent of the rim of the base along the normal
+            let along = normal.dot(axis);
+            let rim = radius * float::sqrt((normal.magnitude2() - along * along).max(S::zero()));
+
+            let far = apex.max(center + rim);
+            let near = apex.min(center - rim);
+            (far >= S::zero(), near >= S::zero())
+        })
+    }
+
+    /// Clips a ray against the frustum. Returns the interval `(enter, exit)` of values of the
+    /// parameter of the ray (see `Ray::at`) where it's inside of the frustum, or `None` if it
+    /// misses it.
+    ///
+    /// `enter` is zero if the origin of the ray is inside of the frustum, and `exit` is infinite if
+    /// the ray never leaves it, which may happen when the far plane lies at infinity.
+    pub fn clip_ray<T>(&self, ray: T) -> Option<(S, S)>
+    where
+        T: Into<Ray<S>>,
+    {
+        let ray = ray.into();
+        self.clip_line_interval(ray.origin, ray.direction, S::zero(), S::infinity())
+    }
+
+    /// Same as `clip_ray`, but clips the whole line the ray lies on, so the interval may start at
+    /// negative values of the parameter.
+    pub fn clip_line<T>(&self, line: T) -> Option<(S, S)>
+    where
+        T: Into<Ray<S>>,
+    {
+        let line = line.into();
+        self.clip_line_interval(line.origin, line.direction, S::neg_infinity(), S::infinity())
+    }
+
+    /// Clips a segment against the frustum. Returns the interval `(enter, exit)` of values of the
+    /// parameter of the segment (see `Segment::at`) where it's inside of the frustum, which lies
+    /// between zero and one, or `None` if it's fully outside.
+    pub fn clip_segment<T>(&self, segment: T) -> Option<(S, S)>
+    where
+        T: Into<Segment<S>>,
+    {
+        let segment = segment.into();
+        let direction = segment.end - segment.start;
+        self.clip_line_interval(segment.start, direction, S::zero(), S::one())
+    }
+
+    /// Returns the portion of a segment that is inside of the frustum, or `None` if it's fully
+    /// outside.
+    pub fn visible_segment<T>(&self, segment: T) -> Option<Segment<S>>
+    where
+        T: Into<Segment<S>>,
+    {
+        let segment = segment.into();
+        self.clip_segment(segment)
+            .map(|(enter, exit)| Segment::from_params(segment.at(enter), segment.at(exit)))
+    }
+
+    /// Clips the points `origin + direction * t`, for `t` between `min` and `max`, against the
+    /// planes of the frustum, returning the interval of `t` that remains.
+    fn clip_line_interval(
+        &self,
+        origin: Vector3<S>,
+        direction: Vector3<S>,
+        mut min: S,
+        mut max: S,
+    ) -> Option<(S, S)> {
+        let tested = self.plane_mask();
+        for i in 0..6 {
+            if tested & (1 << i) == 0 {
+                continue;
+            }
+
+            let (a, b, c, d) = self.plane_coefficients(i);
+            let normal = Vector3::new(a, b, c);
+            let dist = normal.dot(origin) + d;
+            let rate = normal.dot(direction);
+            if rate == S::zero() {
+                // parallel to the plane
+                let visible = dist >= S::zero();
+                if !visible {
+                    return None;
+                }
+                continue;
+            }
+
+            let t = -dist / rate;
+            if rate > S::zero() {
+                min = min.max(t);
+            } else {
+                max = max.min(t);
+            }
+            if min > max {
+                return None;
+            }
+        }
+
+        let valid = min <= max;
+        if valid {
+            Some((min, max))
+        } else {
+            None
+        }
+    }
+
+    /// Clips a triangle against the planes of the frustum.
+    ///
+    /// Clears `out` and fills it with the vertices of the convex polygon that remains, in the same
+    /// winding order, each one paired with its barycentric coordinates relative to the triangle.
+    /// Vertex attributes can be interpolated with them. `out` is left empty if the triangle is
+    /// fully outside of the frustum.
+    ///
+    /// `scratch` is only used as a temporary buffer, so that reusing it and `out` between calls
+    /// avoids allocations.
+    pub fn clip_triangle(
+        &self,
+        triangle: [Vector3<S>; 3],
+        out: &mut Vec<(Vector3<S>, Vector3<S>)>,
+        scratch: &mut Vec<(Vector3<S>, Vector3<S>)>,
+    ) {
+        out.clear();
+        out.push((triangle[0], Vector3::unit_x()));
+        out.push((triangle[1], Vector3::unit_y()));
+        out.push((triangle[2], Vector3::unit_z()));
+
+        let tested = self.plane_mask();
+        for i in 0..6 {
+            if tested & (1 << i) == 0 || out.is_empty() {
+                continue;
+            }
+
+            let plane = self.plane(i);
+            scratch.clear();
+            for current in 0..out.len() {
+                let (a, wa) = out[current];
+                let (b, wb) = out[(current + 1) % out.len()];
+                let (keep, crossing) = clip_edge(plane.distance_to(a), plane.distance_to(b));
+                if keep {
+                    scratch.push((a, wa));
+                }
+                if let Some(t) = crossing {
+                    scratch.push((a + (b - a) * t, wa + (wb - wa) * t));
+                }
+            }
+            mem::swap(out, scratch);
+        }
+    }
+
+    /// Clips a convex polygon against the planes of the frustum, using the Sutherland-Hodgman
+    /// algorithm.
+    ///
+    /// Clears `vertices` and `weights`, and fills `vertices` with the vertices of the convex
+    /// polygon that remains, in the same winding order. For each of them, `polygon.len()` weights
+    /// are pushed to `weights`, so that the vertex is the weighted sum of the vertices of
+    /// `polygon`. Both are left empty if the polygon is fully outside of the frustum.
+    ///
+    /// `scratch_vertices` and `scratch_weights` are only used as temporary buffers, so that
+    /// reusing them, `vertices` and `weights` between calls avoids allocations.
+    pub fn clip_polygon(
+        &self,
+        polygon: &[Vector3<S>],
+        vertices: &mut Vec<Vector3<S>>,
+        weights: &mut Vec<S>,
+        scratch_vertices: &mut Vec<Vector3<S>>,
+        scratch_weights: &mut Vec<S>,
+    ) {
+        let stride = polygon.len();
+        vertices.clear();
+        weights.clear();
+        vertices.extend_from_slice(polygon);
+        for i in 0..stride {
+            weights.extend((0..stride).map(|j| if i == j { S::one() } else { S::zero() }));
+        }
+
+        let tested = self.plane_mask();
+        for i in 0..6 {
+            if tested & (1 << i) == 0 || vertices.is_empty() {
+                continue;
+            }
+
+            let plane = self.plane(i);
+            scratch_vertices.clear();
+            scratch_weights.clear();
+            for current in 0..vertices.len() {
+                let next = (current + 1) % vertices.len();
+                let (a, b) = (vertices[current], vertices[next]);
+                let (keep, crossing) = clip_edge(plane.distance_to(a), plane.distance_to(b));
+                if keep {
+                    scratch_vertices.push(a);
+                    scratch_weights.extend_from_slice(&weights[current * stride..][..stride]);
+                }
+                if let Some(t) = crossing {
+                    scratch_vertices.push(a + (b - a) * t);
+                    for k in 0..stride {
+                        let (wa, wb) = (weights[current * stride + k], weights[next * stride + k]);
+                        scratch_weights.push(wa + (wb - wa) * t);
+                    }
+                }
+            }
+            mem::swap(vertices, scratch_vertices);
+            mem::swap(weights, scratch_weights);
+        }
+    }
+
+    /// Tests wether a given oriented bounding box intersects with the frustum. Like in the case of
+    /// `test_bounding_box`, partial and full intersections are told apart.
+    pub fn test_obb<T>(&self, obb: T) -> Intersection
+    where
+        T: Into<OrientedBoundingBox<S>>,
+    {
+        let obb = obb.into();
+
+        let tested = self.plane_mask();
+        let mut inside = true;
+        for i in 0..6 {
+            if tested & (1 << i) == 0 {
+                continue;
+            }
+
+            let (a, b, c, d) = self.plane_coefficients(i);
+            let normal = Vector3::new(a, b, c);
+
+            let dist = normal.dot(obb.center) + d;
+            let radius = obb.half_extents.x * normal.dot(obb.rotation.x).abs()
+                + obb.half_extents.y * normal.dot(obb.rotation.y).abs()
+                + obb.half_extents.z * normal.dot(obb.rotation.z).abs();
+
+            let visible = dist >= -radius;
+            if !visible {
+                return Intersection::Outside;
+            }
+            inside &= dist >= radius;
+        }
+
+        if inside {
+            Intersection::Inside
+        } else {
+            Intersection::Partial
+        }
+    }
+
+    /// Tests wether a given axis aligned bounding box intersects with the frustum, without the
+    /// false positives of `test_bounding_box`.
+    ///
+    /// The plane test reports boxes that are outside the frustum but not fully behind any single
+    /// plane, such as large boxes near its edges and corners, as partially visible. When it does,
+    /// this method also runs the separating axis tests on the axes of the box and on the cross
+    /// products of the edges of the box and of the frustum, and returns `Outside` if one of them
+    /// separates both volumes. It's several times slower, so it's meant for large or expensive
+    /// objects only.
+    ///
+    /// If the corners of the frustum can't be computed (see `corners`), such as for a far plane at
+    /// infinity, the result of the plane test is returned.
+    pub fn test_bounding_box_exact<T>(&self, aab: T) -> Intersection
+    where
+        T: Into<BoundingBox<S>>,
+    {
+        let aab = aab.into();
+        let result = self.test_bounding_box(aab);
+        if result != Intersection::Partial {
+            return result;
+        }
+        let corners = match self.corners() {
+            Ok(corners) => corners,
+            Err(_) => return result,
+        };
+
+        let center = (aab.min + aab.max) * S::from(0.5).unwrap();
+        let half_extents = aab.max - center;
+        let separated = |axis: Vector3<S>| {
+            let (min, max) = project(&corners, axis);
+            let dist = axis.dot(center);
+            let radius = half_extents.x * axis.x.abs() + half_extents.y * axis.y.abs()
+                + half_extents.z * axis.z.abs();
+            min > dist + radius || max < dist - radius
+        };
+
+        for axis in [Vector3::unit_x(), Vector3::unit_y(), Vector3::unit_z()].iter() {
+            if separated(*axis) {
+                return Intersection::Outside;
+            }
+            for edge in FRUSTUM_EDGES.iter() {
+                let direction = corners[edge.1] - corners[edge.0];
+                let cross = axis.cross(direction);
+                // edges parallel to the axis don't give a new axis
+                if cross.magnitude2() > S::epsilon() * direction.magnitude2() && separated(cross) {
+                    return Intersection::Outside;
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Tests wether the volume of another frustum, such as the one of a shadow casting light or
+    /// of another camera, intersects with the frustum. `Inside` means that the other frustum is
+    /// fully contained in this one.
+    ///
+    /// The separating axis tests are run on the plane normals of both frusta and on the cross
+    /// products of their edges, so the result is exact. If the corners of either frustum can't be
+    /// computed (see `corners`), such as for a far plane at infinity, only the tests that don't
+    /// need them are run, and the result may be `Partial` for frusta that don't intersect.
+    pub fn test_frustum(&self, other: &FrustumCuller<S>) -> Intersection {
+        let corners = self.corners();
+        let other_corners = other.corners();
+
+        if let Ok(ref other_corners) = other_corners {
+            let tested = self.plane_mask();
+            let mut inside = true;
+            for (i, plane) in self.planes().iter().enumerate() {
+                if tested & (1 << i) == 0 {
+                    continue;
+                }
+                let (min, max) = project(other_corners, plane.normal);
+                let visible = max + plane.distance >= S::zero();
+                if !visible {
+                    return Intersection::Outside;
+                }
+                inside &= min + plane.distance >= S::zero();
+            }
+            if inside {
+                return Intersection::Inside;
+            }
+        }
+
+        if let Ok(ref corners) = corners {
+            for plane in other.planes().iter() {
+                let (_, max) = project(corners, plane.normal);
+                let visible = max + plane.distance >= S::zero();
+                if !visible {
+                    return Intersection::Outside;
+                }
+            }
+        }
+
+        let (corners, other_corners) = match (corners, other_corners) {
+            (Ok(corners), Ok(other_corners)) => (corners, other_corners),
+            _ => return Intersection::Partial,
+        };
+        for edge in FRUSTUM_EDGES.iter() {
+            let direction = corners[edge.1] - corners[edge.0];
+            for other_edge in FRUSTUM_EDGES.iter() {
+                let other_direction = other_corners[other_edge.1] - other_corners[other_edge.0];
+                let axis = direction.cross(other_direction);
+                // parallel edges don't give a new axis
+                let parallel = axis.magnitude2()
+                    <= S::epsilon() * direction.magnitude2() * other_direction.magnitude2();
+                if parallel {
+                    continue;
+                }
+                let (min, max) = project(&corners, axis);
+                let (other_min, other_max) = project(&other_corners, axis);
+                if min > other_max || max < other_min {
+                    return Intersection::Outside;
+                }
+            }
+        }
+
+        Intersection::Partial
+    }
+
+    /// Returns the corners of the convex volume shared by this frustum and another one, or an
+    /// empty vector if they don't intersect. Fitting a light projection around them gives tighter
+    /// shadow bounds than using the corners of either frustum.
+    ///
+    /// The corners are found by clipping the faces of each frustum against the other one, and
+    /// copies of the same corner are removed, so their number depends on how the frusta intersect.
+    ///
+    /// Fails if the corners of either frustum can't be computed (see `corners`).
+    pub fn intersection_corners(
+        &self,
+        other: &FrustumCuller<S>,
+    ) -> Result<Vec<Vector3<S>>, FrustumError> {
+        let corners = self.corners()?;
+        let other_corners = other.corners()?;
+
+        // copies of a corner computed from different faces differ by rounding errors, which grow
+        // with the distance from the origin. This is the square of a relative tolerance of
+        // `sqrt(epsilon)`.
+        let tolerance2 = S::epsilon();
+        let mut result: Vec<Vector3<S>> = Vec::new();
+        let mut vertices = Vec::new();
+        let mut weights = Vec::new();
+        let mut scratch_vertices = Vec::new();
+        let mut scratch_weights = Vec::new();
+        let pairs = [(self, &other_corners), (other, &corners)];
+        for &(culler, corners) in pairs.iter() {
+            for face in FRUSTUM_FACES.iter() {
+                let polygon = [
+                    corners[face[0]],
+                    corners[face[1]],
+                    corners[face[2]],
+                    corners[face[3]],
+                ];
+                culler.clip_polygon(
+                    &polygon,
+                    &mut vertices,
+                    &mut weights,
+                    &mut scratch_vertices,
+                    &mut scratch_weights,
+                );
+                for vertex in vertices.iter() {
+                    let limit = tolerance2 * vertex.magnitude2().max(S::one());
+                    let duplicate = result
+                        .iter()
+                        .any(|corner| (corner - vertex).magnitude2() <= limit);
+                    if !duplicate {
+                        result.push(*vertex);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Returns the eight corners of the frustum, computed by intersecting its planes in threes.
+    /// If the culler was created from a view-projection matrix, the corners are in world space.
+    ///
+    /// Corner `i` lies on the left or right plane (depending on bit 0 of `i`), on the bottom or
+    /// top plane (bit 1) and on the near or far plane (bit 2). For example, corner `0` is the
+    /// left-bottom-near one, and corner `7` is the right-top-far one.
+    ///
+    /// Returns an error if any of the planes is not finite (such as the far plane of an infinite
+    /// projection), or if three of the planes don't meet at a single point.
+    pub fn corners(&self) -> Result<[Vector3<S>; 8], FrustumError> {
+        let planes = self.planes();
+        for plane in planes.iter() {
+            let finite = plane.normal.x.is_finite() && plane.normal.y.is_finite()
+                && plane.normal.z.is_finite() && plane.distance.is_finite();
+            if !finite {
+                return Err(FrustumError::NonFinitePlane);
+            }
+        }
+
+        let mut corners = [Vector3::zero(); 8];
+        for (i, corner) in corners.iter_mut().enumerate() {
+            *corner = intersect_planes(
+                &planes[i & 1],
+                &planes[2 + ((i >> 1) & 1)],
+                &planes[4 + ((i >> 2) & 1)],
+            )?;
+        }
+        Ok(corners)
+    }
+
+    /// Returns the smallest axis aligned bounding box that contains the frustum.
+    ///
+    /// Fails under the same conditions as the `corners` method.
+    pub fn bounding_box(&self) -> Result<BoundingBox<S>, FrustumError> {
+        let corners = self.corners()?;
+        let mut min = corners[0];
+        let mut max = corners[0];
+        for corner in corners[1..].iter() {
+            min.x = min.x.min(corner.x);
+            min.y = min.y.min(corner.y);
+            min.z = min.z.min(corner.z);
+            max.x = max.x.max(corner.x);
+            max.y = max.y.max(corner.y);
+            max.z = max.z.max(corner.z);
+        }
+        Ok(BoundingBox::from_params(min, max))
+    }
+
+    /// Returns a bounding sphere of the frustum.
+    ///
+    /// The center is placed on the segment joining the centers of the near and far faces, at the
+    /// point that balances the distance to both faces, which gives the minimal sphere for frusta
+    /// built from symmetric projections. The radius always encloses all eight corners.
+    ///
+    /// Fails under the same conditions as the `corners` method.
+    pub fn bounding_sphere(&self) -> Result<Sphere<S>, FrustumError> {
+        let corners = self.corners()?;
+        let quarter = S::from(0.25).unwrap();
+        let near = (corners[0] + corners[1] + corners[2] + corners[3]) * quarter;
+        let far = (corners[4] + corners[5] + corners[6] + corners[7]) * quarter;
+
+        let near_radius2 = corners[..4]
+            .iter()
+            .fold(S::zero(), |r, c| r.max((c - near).magnitude2()));
+        let far_radius2 = corners[4..]
+            .iter()
+            .fold(S::zero(), |r, c| r.max((c - far).magnitude2()));
+
+        let axis = far - near;
+        let length2 = axis.magnitude2();
+        let t = if length2 > S::zero() {
+            let two = S::one() + S::one();
+            ((far_radius2 - near_radius2 + length2) / (two * length2))
+                .max(S::zero())
+                .min(S::one())
+        } else {
+            S::zero()
+        };
+
+        let center = near + axis * t;
+        let radius2 = corners
+            .iter()
+            .fold(S::zero(), |r, c| r.max((c - center).magnitude2()));
+        let radius = float::sqrt(radius2);
+        Ok(Sphere::from_params(center, radius))
+    }
+
+    /// Returns true if the planes of the frustum are finite, all of them but a far plane at
+    /// infinity have a non-zero normal, and they enclose some volume.
+    ///
+    /// A culler that is not valid, such as one created from a singular matrix, gives meaningless
+    /// results.
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Checks the conditions of `is_valid`, returning the first one that is not met.
+    fn validate(&self) -> Result<(), FrustumError> {
+        let planes = self.planes();
+        for (i, plane) in planes.iter().enumerate() {
+            let (n, d) = (plane.normal, plane.distance);
+            if n.x.is_nan() || n.y.is_nan() || n.z.is_nan() || d.is_nan() {
+                return Err(FrustumError::NonFinitePlane);
+            }
+            if n == Vector3::zero() {
+                if i == 5 && d == S::infinity() {
+                    continue;
+                } else if i == 5 && d == S::neg_infinity() {
+                    return Err(FrustumError::InvertedFrustum);
+                }
+                return Err(FrustumError::ZeroLengthNormal);
+            }
+            let finite = n.x.is_finite() && n.y.is_finite() && n.z.is_finite() && d.is_finite();
+            if !finite {
+                return Err(FrustumError::NonFinitePlane);
+            }
+        }
+
+        // a point that lies inside of the planes of any frustum that encloses some volume: the
+        // center of its corners, or the center of the near face if the far plane lies at infinity
+        let (center, tested) = if self.has_far_plane() {
+            let corners = self.corners()?;
+            let sum = corners.iter().fold(Vector3::zero(), |sum, &c| sum + c);
+            (sum / S::from(8).unwrap(), 6)
+        } else {
+            let mut sum = Vector3::zero();
+            for i in 0..4 {
+                sum += intersect_planes(&planes[i & 1], &planes[2 + (i >> 1)], &planes[4])?;
+            }
+            (sum / S::from(4).unwrap(), 4)
+        };
+        for plane in planes[..tested].iter() {
+            let inside = plane.distance_to(center) > S::zero();
+            if !inside {
+                return Err(FrustumError::InvertedFrustum);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds a plane from the coefficients of a row combination of a projection matrix, scaled so
+/// that the normal has unit length. If the normal is zero the plane lies at infinity, and it's
+/// given an infinite distance that either accepts or rejects every point.
+fn normalize_plane<S: BaseFloat>(plane: Vector4<S>) -> Plane<S> {
+    let normal = plane.truncate();
+    let length2 = normal.magnitude2();
+    if length2 == S::zero() {
+        let distance = if plane.w >= S::zero() {
+            S::infinity()
+        } else {
+            S::neg_infinity()
+        };
+        return Plane::from_params(normal, distance);
+    }
+
+    let invl = float::sqrt(length2).recip();
+    Plane::from_params(normal * invl, plane.w * invl)
+}
+
+/// Step of the Sutherland-Hodgman algorithm for the edge between two vertices at the given signed
+/// distances from a plane.
+///
+/// Returns whether the first vertex is kept, and the interpolation parameter of the point where
+/// the edge crosses the plane, if it does. Vertices on the plane are kept, and an edge only crosses
+/// the plane if its vertices are strictly on opposite sides, so that they aren't emitted twice.
+#[inline]
+fn clip_edge<S: BaseFloat>(da: S, db: S) -> (bool, Option<S>) {
+    let crosses = da > S::zero() && db < S::zero() || da < S::zero() && db > S::zero();
+    (da >= S::zero(), if crosses { Some(da / (da - db)) } else { None })
+}
+
+/// Pairs of indices of the corners returned by `FrustumCuller::corners` joined by an edge.
+const FRUSTUM_EDGES: [(usize, usize); 12] = [
+    (0, 1),
+    (2, 3),
+    (4, 5),
+    (6, 7),
+    (0, 2),
+    (1, 3),
+    (4, 6),
+    (5, 7),
+    (0, 4),
+    (1, 5),
+    (2, 6),
+    (3, 7),
+];
+
+/// Indices of the corners returned by `FrustumCuller::corners` on each face, in the order of
+/// the planes and going around the face.
+const FRUSTUM_FACES: [[usize; 4]; 6] = [
+    [0, 2, 6, 4],
+    [1, 3, 7, 5],
+    [0, 1, 5, 4],
+    [2, 3, 7, 6],
+    [0, 1, 3, 2],
+    [4, 5, 7, 6],
+];
+
+/// Returns the interval covered by the projections of the points on the axis.
+fn project<S: BaseFloat>(points: &[Vector3<S>], axis: Vector3<S>) -> (S, S) {
+    points
+        .iter()
+        .fold((S::infinity(), S::neg_infinity()), |(min, max), point| {
+            let dist = axis.dot(*point);
+            (min.min(dist), max.max(dist))
+        })
+}
+
+/// Computes the point where three planes meet.
+///
+/// The planes are considered parallel when the triple product of their normals is zero relative to
+/// the product of their lengths, so that the test doesn't depend on how the planes are scaled.
+fn intersect_planes<S: BaseFloat>(
+    a: &Plane<S>,
+    b: &Plane<S>,
+    c: &Plane<S>,
+) -> Result<Vector3<S>, FrustumError> {
+    let bc = b.normal.cross(c.normal);
+    let den = a.normal.dot(bc);
+    let scale = float::sqrt(a.normal.magnitude2() * b.normal.magnitude2() * c.normal.magnitude2());
+    if den.abs() <= S::epsilon() * scale {
+        return Err(FrustumError::ParallelPlanes);
+    }
+
+    let ca = c.normal.cross(a.normal);
+    let ab = a.normal.cross(b.normal);
+    Ok((bc * a.distance + ca * b.distance + ab * c.distance) * -den.recip())
+}
+
+impl<S> From<(Vector3<S>, Vector3<S>)> for BoundingBox<S> {
+    #[inline]
+    fn from((min, max): (Vector3<S>, Vector3<S>)) -> Self {
+        Self { min, max }
+    }
+}
+
+impl<S: BaseFloat> From<Sphere<S>> for BoundingBox<S> {
+    /// Returns the smallest bounding box that contains the sphere.
+    #[inline]
+    fn from(sphere: Sphere<S>) -> Self {
+        let extent = Vector3::new(sphere.radius, sphere.radius, sphere.radius);
+        Self::from_params(sphere.center - extent, sphere.center + extent)
+    }
+}
+
+impl<S> From<(Vector3<S>, S)> for Plane<S> {
+    #[inline]
+    fn from((normal, distance): (Vector3<S>, S)) -> Self {
+        Self { normal, distance }
+    }
+}
+
+impl<S> From<(Vector3<S>, Vector3<S>, Matrix3<S>)> for OrientedBoundingBox<S> {
+    #[inline]
+    fn from((center, half_extents, rotation): (Vector3<S>, Vector3<S>, Matrix3<S>)) -> Self {
+        Self {
+            center,
+            half_extents,
+            rotation,
+        }
+    }
+}
+
+impl<S: BaseFloat> From<(Vector3<S>, Vector3<S>, Quaternion<S>)> for OrientedBoundingBox<S> {
+    #[inline]
+    fn from((center, half_extents, rotation): (Vector3<S>, Vector3<S>, Quaternion<S>)) -> Self {
+        Self {
+            center,
+            half_extents,
+            rotation: rotation.into(),
+        }
+    }
+}
+
+impl<S> From<(Vector3<S>, S)> for Sphere<S> {
+    #[inline]
+    fn from((center, radius): (Vector3<S>, S)) -> Self {
+        Self { center, radius }
+    }
+}
+
+#[cfg(feature = "mint")]
+impl<S: Clone> From<(mint::Vector3<S>, mint::Vector3<S>)> for BoundingBox<S> {
+    #[inline]
+    fn from((min, max): (mint::Vector3<S>, mint::Vector3<S>)) -> Self {
+        Self::from((Vector3::from(min), Vector3::from(max)))
+    }
+}
+
+#[cfg(feature = "mint")]
+impl<S: Clone> From<(mint::Vector3<S>, S)> for Plane<S> {
+    #[inline]
+    fn from((normal, distance): (mint::Vector3<S>, S)) -> Self {
+        Self::from((Vector3::from(normal), distance))
+    }
+}
+
+#[cfg(feature = "mint")]
+impl<S: Clone> From<(mint::Vector3<S>, S)> for Sphere<S> {
+    #[inline]
+    fn from((center, radius): (mint::Vector3<S>, S)) -> Self {
+        Self::from((Vector3::from(center), radius))
+    }
+}
+
+impl<S> From<(Vector3<S>, Vector3<S>, S)> for Capsule<S> {
+    #[inline]
+    fn from((start, end, radius): (Vector3<S>, Vector3<S>, S)) -> Self {
+        Self { start, end, radius }
+    }
+}
+
+impl<S> From<(Vector3<S>, Vector3<S>, Rad<S>, S)> for Cone<S> {
+    #[inline]
+    fn from((apex, direction, angle, range): (Vector3<S>, Vector3<S>, Rad<S>, S)) -> Self {
+        Self {
+            apex,
+            direction,
+            angle,
+            range,
+        }
+    }
+}
+
+impl<S> From<(Vector3<S>, Vector3<S>)> for Ray<S> {
+    #[inline]
+    fn from((origin, direction): (Vector3<S>, Vector3<S>)) -> Self {
+        Self { origin, direction }
+    }
+}
+
+impl<S> From<(Vector3<S>, Vector3<S>)> for Segment<S> {
+    #[inline]
+    fn from((start, end): (Vector3<S>, Vector3<S>)) -> Self {
+        Self { start, end }
+    }
+}
+
+impl<S: Scalar> From<Sphere<S>> for Capsule<S> {
+    /// Returns a capsule whose segment is reduced to the center of the sphere.
+    #[inline]
+    fn from(sphere: Sphere<S>) -> Self {
+        Self::from_params(sphere.center, sphere.center, sphere.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use {BoundingBox, Capsule, Cone, DepthRange, FrustumCuller, FrustumError, Intersection,
+         OrientedBoundingBox, Plane, Ray, Segment, Sphere, PLANE_MASK_ALL, PLANE_MASK_PX};
+
+    use cgmath::{Deg, Matrix3, Matrix4, Ortho, PerspectiveFov, Point3, Quaternion, Rad, Vector3,
+                 prelude::*};
+
+    use std::f64;
+
+    #[test]
+    fn sphere_in_frustum_ortho() {
+        let frustum_culling = FrustumCuller::from_matrix(
+            Ortho {
+                left: -1.0,
+                right: 1.0,
+                bottom: -1.0,
+                top: 1.0,
+                near: -1.0,
+                far: 1.0,
+            }.into(),
+        );
+
+        assert_eq!(
+            Intersection::Inside,
+            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(0.0, 0.0, 0.0), 0.1))
+        );
+        assert_eq!(
+            Intersection::Partial,
+            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(1.0, 0.0, 0.0), 0.1))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(1.2, 0.0, 0.0), 0.1))
+        );
+    }
+
+    #[test]
+    fn sphere_in_frustum_perspective() {
+        let frustum_culling = FrustumCuller::from_matrix(
+            PerspectiveFov {
+                fovy: Rad(f64::consts::FRAC_PI_2),
+                aspect: 1.0,
+                near: 0.1,
+                far: 100.0,
+            }.into(),
+        );
+
+        assert_eq!(
+            Intersection::Inside,
+            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(1.0, 0.0, -2.0), 0.1))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(4.0, 0.0, -2.0), 0.1))
+        );
+    }
+
+    #[test]
+    fn test_point_in_perspective() {
+        let frustum_culling = FrustumCuller::from_matrix(
             PerspectiveFov {
-                fovy: Rad(3.14159265 / 2.0),
+                fovy: Rad(f64::consts::FRAC_PI_2),
                 aspect: 1.0,
                 near: 0.1,
                 far: 100.0,
             }.into(),
         );
 
-        assert_eq!(
+        assert_eq!(
+            Intersection::Inside,
+            frustum_culling.test_point(Vector3::new(0.0, 0.0, -5.0))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            frustum_culling.test_point(Vector3::new(0.0, 6.0, -5.0))
+        );
+    }
+
+    #[test]
+    fn test_aab_in_ortho() {
+        let mut c = FrustumCuller::from_matrix(
+            Ortho {
+                left: -1.0,
+                right: 1.0,
+                bottom: -1.0,
+                top: 1.0,
+                near: -1.0,
+                far: 1.0,
+            }.into(),
+        );
+
+        assert_eq!(
+            Intersection::Partial,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(-20.0, -2.0, 0.0),
+                Vector3::new(20.0, 2.0, 0.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Inside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(-0.5, -0.5, -0.5),
+                Vector3::new(0.5, 0.5, 0.5)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(1.1, 0.0, 0.0),
+                Vector3::new(2.0, 2.0, 2.0)
+            ))
+        );
+
+        c = FrustumCuller::from_ortho(Ortho {
+            left: -1.0,
+            right: 1.0,
+            bottom: -1.0,
+            top: 1.0,
+            near: -1.0,
+            far: 1.0,
+        });
+
+        assert_eq!(
+            Intersection::Partial,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(2.0, 2.0, 2.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(1.1, 0.0, 0.0),
+                Vector3::new(2.0, 2.0, 2.0)
+            ))
+        );
+
+        c = FrustumCuller::from_matrix(Matrix4::identity());
+
+        assert_eq!(
+            Intersection::Partial,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(0.5, 0.5, 0.5),
+                Vector3::new(2.0, 2.0, 2.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(1.5, 0.5, 0.5),
+                Vector3::new(2.0, 2.0, 2.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(-2.5, 0.5, 0.5),
+                Vector3::new(-1.5, 2.0, 2.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(-0.5, -2.5, 0.5),
+                Vector3::new(1.5, -2.0, 2.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_aab_in_perspective() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        assert_eq!(
+            Intersection::Inside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(0.0, 0.0, -7.0),
+                Vector3::new(1.0, 1.0, -5.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(1.1, 0.0, 0.0),
+                Vector3::new(2.0, 2.0, 2.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(4.0, 4.0, -3.0),
+                Vector3::new(5.0, 5.0, -5.0)
+            ))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_bounding_box(BoundingBox::from_params(
+                Vector3::new(-6.0, -6.0, -2.0),
+                Vector3::new(-1.0, -4.0, -4.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_masked_matches_unmasked() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        let boxes = [
+            BoundingBox::from_params(Vector3::new(0.0, 0.0, -7.0), Vector3::new(1.0, 1.0, -5.0)),
+            BoundingBox::from_params(Vector3::new(4.0, 4.0, -5.0), Vector3::new(5.0, 5.0, -3.0)),
+            BoundingBox::from_params(Vector3::new(-1.0, -1.0, -1.0), Vector3::new(1.0, 1.0, 1.0)),
+        ];
+        for aab in boxes.iter() {
+            assert_eq!(
+                c.test_bounding_box(*aab),
+                c.test_bounding_box_masked(*aab, PLANE_MASK_ALL).0
+            );
+        }
+
+        let spheres = [
+            Sphere::from_params(Vector3::new(1.0, 0.0, -2.0), 0.1),
+            Sphere::from_params(Vector3::new(4.0, 0.0, -2.0), 0.1),
+            Sphere::from_params(Vector3::new(0.0, 0.0, 0.0), 1.0),
+        ];
+        for sphere in spheres.iter() {
+            assert_eq!(
+                c.test_sphere(*sphere),
+                c.test_sphere_masked(*sphere, PLANE_MASK_ALL).0
+            );
+        }
+    }
+
+    #[test]
+    fn test_mask_propagation() {
+        let c = FrustumCuller::from_ortho(Ortho {
+            left: -1.0,
+            right: 1.0,
+            bottom: -1.0,
+            top: 1.0,
+            near: -1.0,
+            far: 1.0,
+        });
+
+        // straddles the right plane only
+        let parent =
+            BoundingBox::from_params(Vector3::new(0.0, -0.5, -0.5), Vector3::new(2.0, 0.5, 0.5));
+        let (intersection, mask) = c.test_bounding_box_masked(parent, PLANE_MASK_ALL);
+        assert_eq!(Intersection::Partial, intersection);
+        assert_eq!(PLANE_MASK_PX, mask);
+
+        let child =
+            BoundingBox::from_params(Vector3::new(0.0, -0.5, -0.5), Vector3::new(0.5, 0.5, 0.5));
+        assert_eq!(
+            (Intersection::Inside, 0),
+            c.test_bounding_box_masked(child, mask)
+        );
+
+        let child =
+            BoundingBox::from_params(Vector3::new(1.5, -0.5, -0.5), Vector3::new(2.0, 0.5, 0.5));
+        assert_eq!(
+            (Intersection::Outside, mask),
+            c.test_bounding_box_masked(child, mask)
+        );
+
+        // planes left out of the mask are not tested at all
+        assert_eq!(
+            (Intersection::Inside, 0),
+            c.test_sphere_masked(Sphere::from_params(Vector3::new(5.0, 0.0, 0.0), 0.1), 0)
+        );
+    }
+
+    #[test]
+    fn test_planes() {
+        let c = FrustumCuller::from_ortho(Ortho {
+            left: -1.0,
+            right: 2.0,
+            bottom: -3.0,
+            top: 4.0,
+            near: 0.5,
+            far: 10.0,
+        });
+
+        let expected = [
+            Plane::from_params(Vector3::new(1.0, 0.0, 0.0), 1.0),
+            Plane::from_params(Vector3::new(-1.0, 0.0, 0.0), 2.0),
+            Plane::from_params(Vector3::new(0.0, 1.0, 0.0), 3.0),
+            Plane::from_params(Vector3::new(0.0, -1.0, 0.0), 4.0),
+            Plane::from_params(Vector3::new(0.0, 0.0, -1.0), -0.5),
+            Plane::from_params(Vector3::new(0.0, 0.0, 1.0), 10.0),
+        ];
+        for (plane, expected) in c.planes().iter().zip(expected.iter()) {
+            assert_relative_eq!(plane.normal, expected.normal, epsilon = 1e-6);
+            assert_relative_eq!(plane.distance, expected.distance, epsilon = 1e-6);
+        }
+        assert_eq!(c.planes()[3], c.plane(3));
+        assert_eq!(c, FrustumCuller::from_planes(c.planes()));
+    }
+
+    #[test]
+    fn test_from_planes() {
+        // unit cube centered at the origin
+        let c = FrustumCuller::from_planes([
+            (Vector3::new(1.0, 0.0, 0.0), 0.5).into(),
+            (Vector3::new(-1.0, 0.0, 0.0), 0.5).into(),
+            (Vector3::new(0.0, 1.0, 0.0), 0.5).into(),
+            (Vector3::new(0.0, -1.0, 0.0), 0.5).into(),
+            (Vector3::new(0.0, 0.0, 1.0), 0.5).into(),
+            (Vector3::new(0.0, 0.0, -1.0), 0.5).into(),
+        ]);
+
+        assert_eq!(
+            Intersection::Inside,
+            c.test_point(Vector3::new(0.25, -0.25, 0.25))
+        );
+        assert_eq!(
+            Intersection::Outside,
+            c.test_point(Vector3::new(0.0, 0.0, 0.75))
+        );
+        assert_eq!(
+            Intersection::Partial,
+            c.test_sphere(Sphere::from_params(Vector3::new(0.5, 0.0, 0.0), 0.1))
+        );
+        assert_eq!(
+            0.25,
+            c.plane(0).distance_to(Vector3::new(-0.25, 0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_corners_ortho() {
+        let c = FrustumCuller::from_ortho(Ortho {
+            left: -1.0,
+            right: 2.0,
+            bottom: -3.0,
+            top: 4.0,
+            near: 0.5,
+            far: 10.0,
+        });
+
+        let corners = c.corners().unwrap();
+        assert_relative_eq!(corners[0], Vector3::new(-1.0, -3.0, -0.5), epsilon = 1e-6);
+        assert_relative_eq!(corners[3], Vector3::new(2.0, 4.0, -0.5), epsilon = 1e-6);
+        assert_relative_eq!(corners[5], Vector3::new(2.0, -3.0, -10.0), epsilon = 1e-6);
+        assert_relative_eq!(corners[6], Vector3::new(-1.0, 4.0, -10.0), epsilon = 1e-6);
+
+        let aab = c.bounding_box().unwrap();
+        assert_relative_eq!(aab.min, Vector3::new(-1.0, -3.0, -10.0), epsilon = 1e-6);
+        assert_relative_eq!(aab.max, Vector3::new(2.0, 4.0, -0.5), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_bounding_sphere_perspective() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 1.0,
+            far: 10.0,
+        });
+
+        let corners = c.corners().unwrap();
+        assert_relative_eq!(corners[0], Vector3::new(-1.0, -1.0, -1.0), epsilon = 1e-6);
+        assert_relative_eq!(corners[7], Vector3::new(10.0, 10.0, -10.0), epsilon = 1e-6);
+
+        let sphere = c.bounding_sphere().unwrap();
+        for corner in corners.iter() {
+            assert!((corner - sphere.center).magnitude() <= sphere.radius + 1e-6);
+        }
+        // the far face is the widest, so the sphere is centered on it
+        assert_relative_eq!(sphere.center, Vector3::new(0.0, 0.0, -10.0), epsilon = 1e-6);
+        assert_relative_eq!(sphere.radius, 200.0_f64.sqrt(), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_corners_degenerate() {
+        // perspective projection with an infinite far plane
+        let near = 0.1;
+        let infinite = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, -1.0, -1.0,
+            0.0, 0.0, -2.0 * near, 0.0,
+        );
+        let c = FrustumCuller::from_matrix(infinite);
+        assert_eq!(Err(FrustumError::NonFinitePlane), c.corners());
+        assert_eq!(Err(FrustumError::NonFinitePlane), c.bounding_sphere());
+
+        // near plane parallel to the left plane
+        let mut planes = FrustumCuller::<f64>::new().planes();
+        planes[4] = planes[0];
+        let c = FrustumCuller::from_planes(planes);
+        assert_eq!(Err(FrustumError::ParallelPlanes), c.bounding_box());
+
+        // the test for parallel planes doesn't depend on the scale of the planes (scaling them by
+        // powers of two leaves the corners unchanged)
+        let unit = FrustumCuller::<f64>::new();
+        for &scale in [0.5f64.powi(30), 2f64.powi(30)].iter() {
+            let mut planes = unit.planes();
+            for plane in planes.iter_mut() {
+                *plane = Plane::from_params(plane.normal * scale, plane.distance * scale);
+            }
+            let c = FrustumCuller::from_planes(planes);
+            assert_eq!(unit.corners(), c.corners());
+
+            planes[4] = Plane::from_params(
+                (unit.planes()[0].normal + Vector3::new(0.0, 0.0, 1e-16)).normalize() * scale,
+                planes[4].distance,
+            );
+            let c = FrustumCuller::from_planes(planes);
+            assert_eq!(Err(FrustumError::ParallelPlanes), c.corners());
+        }
+    }
+
+    #[test]
+    fn test_obb_in_perspective() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        // a long thin box just outside of the right plane, and parallel to it. Its axis aligned
+        // bounding box overlaps the frustum, but the box itself doesn't.
+        let rotation = Matrix3::from_angle_y(Deg(45.0));
+        let obb = OrientedBoundingBox::from_params(
+            Vector3::new(6.0, 0.0, -5.0),
+            Vector3::new(3.0, 0.1, 0.1),
+            rotation,
+        );
+        let corner = Vector3::new(2.2, 0.1, 2.2);
+        assert_eq!(
+            Intersection::Partial,
+            c.test_bounding_box(BoundingBox::from_params(obb.center - corner, obb.center + corner))
+        );
+        assert_eq!(Intersection::Outside, c.test_obb(obb));
+
+        assert_eq!(
+            Intersection::Inside,
+            c.test_obb((
+                Vector3::new(0.0, 0.0, -5.0),
+                Vector3::new(1.0, 2.0, 1.0),
+                Quaternion::from_angle_y(Deg(30.0)),
+            ))
+        );
+        assert_eq!(
+            Intersection::Partial,
+            c.test_obb((
+                Vector3::new(5.0, 0.0, -5.0),
+                Vector3::new(1.0, 1.0, 1.0),
+                Matrix3::identity(),
+            ))
+        );
+    }
+
+    #[test]
+    fn test_aab_exact() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        // a box beyond the corner between the left and far planes, which is on the visible side
+        // of both of them
+        let aab = BoundingBox::from_params(
+            Vector3::new(-130.0, -1.0, -110.0),
+            Vector3::new(-105.0, 1.0, -90.0),
+        );
+        assert_eq!(Intersection::Partial, c.test_bounding_box(aab));
+        assert_eq!(Intersection::Outside, c.test_bounding_box_exact(aab));
+
+        let aab = BoundingBox::from_params(
+            Vector3::new(-100.0, -1.0, -110.0),
+            Vector3::new(-95.0, 1.0, -90.0),
+        );
+        assert_eq!(Intersection::Partial, c.test_bounding_box_exact(aab));
+        let aab = BoundingBox::from_params(
+            Vector3::new(-1.0, -1.0, -6.0),
+            Vector3::new(1.0, 1.0, -4.0),
+        );
+        assert_eq!(Intersection::Inside, c.test_bounding_box_exact(aab));
+        let aab = BoundingBox::from_params(
+            Vector3::new(-1.0, -1.0, 1.0),
+            Vector3::new(1.0, 1.0, 2.0),
+        );
+        assert_eq!(Intersection::Outside, c.test_bounding_box_exact(aab));
+
+        // without far plane, the box does intersect the frustum
+        let c =
+            FrustumCuller::from_infinite_perspective_fov(Rad(f64::consts::FRAC_PI_2), 1.0, 0.1);
+        let aab = BoundingBox::from_params(
+            Vector3::new(-130.0, -1.0, -110.0),
+            Vector3::new(-105.0, 1.0, -90.0),
+        );
+        assert_eq!(Intersection::Partial, c.test_bounding_box_exact(aab));
+
+        // boxes reported as outside don't contain any point inside the frustum
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(1.0),
+            aspect: 1.5,
+            near: 1.0,
+            far: 20.0,
+        });
+        let wave = |i: f64, a: f64, b: f64, c: f64| {
+            Vector3::new((i * a).sin(), (i * b).sin(), (i * c).sin())
+        };
+        let mut rejected = 0;
+        for i in 0..2000 {
+            let i = f64::from(i);
+            let center = wave(i, 0.37, 0.53, 0.71).mul_element_wise(Vector3::new(25.0, 15.0, 25.0))
+                - Vector3::new(0.0, 0.0, 5.0);
+            let half = wave(i, 0.13, 0.29, 0.41).map(|x| 1.0 + x.abs() * 4.0);
+            let aab = BoundingBox::from_params(center - half, center + half);
+            let fast = c.test_bounding_box(aab);
+            let exact = c.test_bounding_box_exact(aab);
+            if fast != Intersection::Partial {
+                assert_eq!(fast, exact);
+            } else if exact == Intersection::Outside {
+                rejected += 1;
+                for j in 0..1000 {
+                    let t = Vector3::new(j % 10, j / 10 % 10, j / 100).map(|x| f64::from(x) / 9.0);
+                    let point = aab.min + (aab.max - aab.min).mul_element_wise(t);
+                    assert_eq!(Intersection::Outside, c.test_point(point));
+                }
+            }
+        }
+        assert!(rejected > 0);
+    }
+
+    #[test]
+    fn test_frustum() {
+        let projection = PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        };
+        let c = FrustumCuller::from_perspective_fov(projection);
+
+        let narrow = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(1.0),
+            aspect: 1.0,
+            near: 1.0,
+            far: 50.0,
+        });
+        assert_eq!(Intersection::Inside, c.test_frustum(&narrow));
+        assert_eq!(Intersection::Partial, narrow.test_frustum(&c));
+        let corners = c.intersection_corners(&narrow).unwrap();
+        assert_eq!(8, corners.len());
+        for corner in narrow.corners().unwrap().iter() {
+            assert!(corners.iter().any(|c| c.distance(*corner) < 1e-9));
+        }
+
+        // looking the other way, from behind
+        let behind = FrustumCuller::from_matrix(
+            Matrix4::from(projection) * Matrix4::from_translation(Vector3::new(0.0, 0.0, -200.0)),
+        );
+        assert_eq!(Intersection::Outside, c.test_frustum(&behind));
+        assert!(c.intersection_corners(&behind).unwrap().is_empty());
+
+        // two overlapping boxes share a box
+        let ortho = Ortho {
+            left: -1.0,
+            right: 1.0,
+            bottom: -1.0,
+            top: 1.0,
+            near: -1.0,
+            far: 1.0,
+        };
+        let cube = FrustumCuller::from_ortho(ortho);
+        let shifted = FrustumCuller::from_matrix(
+            Matrix4::from(ortho) * Matrix4::from_translation(Vector3::new(-1.0, 0.0, 0.0)),
+        );
+        assert_eq!(Intersection::Partial, cube.test_frustum(&shifted));
+        let mut corners = cube.intersection_corners(&shifted).unwrap();
+        assert_eq!(8, corners.len());
+        corners.sort_by(|a, b| (a.x, a.y, a.z).partial_cmp(&(b.x, b.y, b.z)).unwrap());
+        for (i, corner) in corners.iter().enumerate() {
+            let expected = Vector3::new(
+                (i >> 2 & 1) as f64,
+                (i >> 1 & 1) as f64 * 2.0 - 1.0,
+                (i & 1) as f64 * 2.0 - 1.0,
+            );
+            assert_relative_eq!(expected, *corner, epsilon = 1e-9);
+        }
+
+        let infinite =
+            FrustumCuller::from_infinite_perspective_fov(Rad(f64::consts::FRAC_PI_2), 1.0, 0.1);
+        assert_eq!(Intersection::Partial, c.test_frustum(&infinite));
+        assert_eq!(Intersection::Inside, infinite.test_frustum(&narrow));
+        assert_eq!(Intersection::Outside, infinite.test_frustum(&behind));
+        assert_eq!(Intersection::Outside, behind.test_frustum(&infinite));
+        assert_eq!(Err(FrustumError::NonFinitePlane), c.intersection_corners(&infinite));
+
+        // the separating axis test agrees with the shared volume
+        let (mut disjoint, mut overlapping) = (0, 0);
+        for i in 1..500 {
+            let i = f64::from(i);
+            let eye = Point3::new((i * 0.37).sin(), (i * 0.53).sin(), (i * 0.71).sin()) * 150.0;
+            let target = Point3::new((i * 0.13).sin(), (i * 0.29).sin(), (i * 0.41).sin()) * 50.0;
+            let other = FrustumCuller::from_matrix(
+                Matrix4::from(PerspectiveFov {
+                    fovy: Rad(0.3 + (i * 0.17).sin().abs()),
+                    aspect: 1.5,
+                    near: 1.0,
+                    far: 60.0,
+                }) * Matrix4::look_at(eye, target, Vector3::unit_y()),
+            );
+            let corners = c.intersection_corners(&other).unwrap();
+            for corner in corners.iter() {
+                for plane in c.planes().iter().chain(other.planes().iter()) {
+                    assert!(plane.normalize().distance_to(*corner) > -1e-6);
+                }
+            }
+            if c.test_frustum(&other) == Intersection::Outside {
+                assert!(corners.is_empty());
+                disjoint += 1;
+            } else {
+                assert!(!corners.is_empty());
+                overlapping += 1;
+            }
+        }
+        assert!(disjoint > 0 && overlapping > 0);
+    }
+
+    // remaps the depth of an OpenGL projection to the given range
+    fn remap_depth(depth_range: DepthRange) -> Matrix4<f64> {
+        let (scale, offset) = match depth_range {
+            DepthRange::NegativeOneToOne => (1.0, 0.0),
+            DepthRange::ZeroToOne => (0.5, 0.5),
+            DepthRange::OneToZero => (-0.5, 0.5),
+        };
+        Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, scale, 0.0,
+            0.0, 0.0, offset, 1.0,
+        )
+    }
+
+    #[test]
+    fn test_depth_ranges() {
+        let projection = Matrix4::from(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+        let expected = FrustumCuller::from_matrix(projection).planes();
+
+        for &depth_range in [DepthRange::ZeroToOne, DepthRange::OneToZero].iter() {
+            let m = remap_depth(depth_range) * projection;
+            let c = FrustumCuller::from_matrix_with_depth_range(m, depth_range);
+            for (plane, expected) in c.planes().iter().zip(expected.iter()) {
+                assert_relative_eq!(plane.normal, expected.normal, epsilon = 1e-9);
+                assert_relative_eq!(plane.distance, expected.distance, epsilon = 1e-9);
+            }
+
+            assert_eq!(Intersection::Inside, c.test_point(Vector3::new(0.0, 0.0, -0.2)));
+            assert_eq!(Intersection::Outside, c.test_point(Vector3::new(0.0, 0.0, -0.07)));
+            assert_eq!(Intersection::Outside, c.test_point(Vector3::new(0.0, 0.0, -101.0)));
+
+            // assuming the OpenGL convention misplaces the near plane in [0, 1], and the far plane
+            // in reversed-Z
+            let wrong = FrustumCuller::from_matrix(m);
+            let misplaced = if depth_range == DepthRange::ZeroToOne {
+                Vector3::new(0.0, 0.0, -0.07)
+            } else {
+                Vector3::new(0.0, 0.0, -101.0)
+            };
+            assert_eq!(Intersection::Inside, wrong.test_point(misplaced));
+        }
+    }
+
+    #[test]
+    fn test_depth_ranges_infinite_far() {
+        let near = 0.1;
+        // [0, 1] and reversed-Z [1, 0] projections with an infinite far plane
+        let zero_to_one = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, -1.0, -1.0,
+            0.0, 0.0, -near, 0.0,
+        );
+        let one_to_zero = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, -1.0,
+            0.0, 0.0, near, 0.0,
+        );
+
+        for &(m, depth_range) in [
+            (zero_to_one, DepthRange::ZeroToOne),
+            (one_to_zero, DepthRange::OneToZero),
+        ].iter()
+        {
+            let c = FrustumCuller::from_matrix_with_depth_range(m, depth_range);
+            let far = c.planes()[5];
+            assert_eq!(Vector3::zero(), far.normal);
+            assert_eq!(f64::INFINITY, far.distance);
+
+            assert_relative_eq!(
+                c.planes()[4].normal,
+                Vector3::new(0.0, 0.0, -1.0),
+                epsilon = 1e-9
+            );
+            assert_relative_eq!(c.planes()[4].distance, -near, epsilon = 1e-9);
+
+            assert_eq!(Intersection::Inside, c.test_point(Vector3::new(0.0, 0.0, -1e12)));
+            assert_eq!(Intersection::Outside, c.test_point(Vector3::new(0.0, 0.0, -0.05)));
+            assert_eq!(
+                Intersection::Inside,
+                c.test_sphere(Sphere::from_params(Vector3::new(0.0, 0.0, -1e6), 1e3))
+            );
+            assert_eq!(
+                Intersection::Inside,
+                c.test_bounding_box(BoundingBox::from_params(
+                    Vector3::new(-1.0, -1.0, -1e9),
+                    Vector3::new(1.0, 1.0, -5.0)
+                ))
+            );
+            assert_eq!(Err(FrustumError::NonFinitePlane), c.corners());
+        }
+    }
+
+    #[test]
+    fn test_infinite_far_plane() {
+        let fovy = Rad(f64::consts::FRAC_PI_2);
+        let near = 0.1;
+        let infinite = FrustumCuller::from_infinite_perspective_fov(fovy, 1.0, near);
+        let reversed = FrustumCuller::from_reversed_z(Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, -1.0,
+            0.0, 0.0, near, 0.0,
+        ));
+        let finite = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy,
+            aspect: 1.0,
+            near,
+            far: 100.0,
+        });
+        assert!(finite.has_far_plane());
+
+        for c in [infinite, reversed].iter() {
+            assert!(!c.has_far_plane());
+            for i in 0..5 {
+                let (plane, expected) = (c.plane(i), finite.plane(i));
+                assert_relative_eq!(plane.normal, expected.normal, epsilon = 1e-9);
+                assert_relative_eq!(plane.distance, expected.distance, epsilon = 1e-9);
+            }
+
+            let far = Vector3::new(0.0, 0.0, f64::NEG_INFINITY);
+            assert_eq!(Intersection::Inside, c.test_point(Vector3::new(0.0, 0.0, -1e12)));
+            assert_eq!(Intersection::Outside, c.test_point(Vector3::new(0.0, 0.0, -0.05)));
+            assert_eq!(
+                Intersection::Inside,
+                c.test_sphere(Sphere::from_params(Vector3::new(0.0, 0.0, -1e6), 1e3))
+            );
+            assert_eq!(
+                Intersection::Partial,
+                c.test_sphere(Sphere::from_params(Vector3::new(0.0, 0.0, -0.1), 0.05))
+            );
+
+            // boxes reaching infinitely far away are not culled by the far plane
+            let aab = BoundingBox::from_params(
+                Vector3::new(-1.0, -1.0, f64::NEG_INFINITY),
+                Vector3::new(1.0, 1.0, -5.0),
+            );
+            assert_eq!(Intersection::Inside, c.test_bounding_box(aab));
+            assert_eq!(
+                (Intersection::Inside, 0),
+                c.test_bounding_box_masked(aab, PLANE_MASK_ALL)
+            );
+            assert_eq!(
+                Intersection::Partial,
+                c.test_bounding_box(BoundingBox::from_params(
+                    far + Vector3::new(-1.0, -1.0, 0.0),
+                    Vector3::new(1.0, 1.0, 0.0)
+                ))
+            );
+            assert_eq!(
+                Intersection::Inside,
+                c.test_obb(OrientedBoundingBox::from_params(
+                    Vector3::new(0.0, 0.0, -1e6),
+                    Vector3::new(1e3, 1e3, 1e3),
+                    Matrix3::from_angle_y(Deg(45.0))
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_try_from_matrix() {
+        let perspective = Matrix4::from(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+        let ortho = Matrix4::from(Ortho {
+            left: -1.0,
+            right: 3.0,
+            bottom: -2.0,
+            top: 1.0,
+            near: -1.0,
+            far: 10.0,
+        });
+
+        for &m in [perspective, ortho, Matrix4::identity()].iter() {
+            let c = FrustumCuller::try_from_matrix(m).unwrap();
+            assert_eq!(FrustumCuller::from_matrix(m), c);
+            assert!(c.is_valid());
+        }
+        assert!(FrustumCuller::<f64>::new().is_valid());
+        assert!(
+            FrustumCuller::from_infinite_perspective_fov(Rad(1.0), 1.5, 0.1).is_valid()
+        );
+
+        let mut nan = perspective;
+        nan.z.z = f64::NAN;
+        assert_eq!(Err(FrustumError::NonFiniteMatrix), FrustumCuller::try_from_matrix(nan));
+        assert!(!FrustumCuller::from_matrix(nan).is_valid());
+
+        let mut inf = perspective;
+        inf.w.x = f64::INFINITY;
+        assert_eq!(Err(FrustumError::NonFiniteMatrix), FrustumCuller::try_from_matrix(inf));
+
+        assert_eq!(
+            Err(FrustumError::ZeroLengthNormal),
+            FrustumCuller::try_from_matrix(Matrix4::<f64>::zero())
+        );
+
+        // flattening the x axis makes the left and right planes coincide
+        let singular = perspective * Matrix4::from_nonuniform_scale(0.0, 1.0, 1.0);
+        assert_eq!(
+            Err(FrustumError::ParallelPlanes),
+            FrustumCuller::try_from_matrix(singular)
+        );
+
+        // negating the matrix turns all the planes around
+        for &m in [perspective, ortho].iter() {
+            assert_eq!(
+                Err(FrustumError::InvertedFrustum),
+                FrustumCuller::try_from_matrix(-m)
+            );
+            assert!(!FrustumCuller::from_matrix(-m).is_valid());
+        }
+        let infinite = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, -1.0, -1.0,
+            0.0, 0.0, -0.2, 0.0,
+        );
+        assert!(FrustumCuller::try_from_matrix(infinite).is_ok());
+        assert_eq!(
+            Err(FrustumError::InvertedFrustum),
+            FrustumCuller::try_from_matrix(-infinite)
+        );
+    }
+
+    /// Integer scalar, used to check that the culling tests don't depend on floats.
+    #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+    struct Int(i64);
+
+    impl ::std::ops::Add for Int {
+        type Output = Int;
+        fn add(self, other: Int) -> Int {
+            Int(self.0 + other.0)
+        }
+    }
+
+    impl ::std::ops::Sub for Int {
+        type Output = Int;
+        fn sub(self, other: Int) -> Int {
+            Int(self.0 - other.0)
+        }
+    }
+
+    impl ::std::ops::Mul for Int {
+        type Output = Int;
+        fn mul(self, other: Int) -> Int {
+            Int(self.0 * other.0)
+        }
+    }
+
+    impl ::std::ops::Neg for Int {
+        type Output = Int;
+        fn neg(self) -> Int {
+            Int(-self.0)
+        }
+    }
+
+    impl ::cgmath::num_traits::Zero for Int {
+        fn zero() -> Int {
+            Int(0)
+        }
+        fn is_zero(&self) -> bool {
+            self.0 == 0
+        }
+    }
+
+    impl ::Scalar for Int {}
+
+    #[test]
+    fn test_non_float_scalar() {
+        let planes = [
+            (Vector3::new(1, 0, 0), 8),
+            (Vector3::new(-1, 0, 0), 8),
+            (Vector3::new(0, 1, 1), 6),
+            (Vector3::new(0, -1, 1), 6),
+            (Vector3::new(0, 0, 1), 4),
+            (Vector3::new(1, 1, -2), 20),
+        ];
+        let int = |v: i64| Int(v);
+        let c = FrustumCuller::from_planes([
+            Plane::from_params(planes[0].0.map(int), Int(planes[0].1)),
+            Plane::from_params(planes[1].0.map(int), Int(planes[1].1)),
+            Plane::from_params(planes[2].0.map(int), Int(planes[2].1)),
+            Plane::from_params(planes[3].0.map(int), Int(planes[3].1)),
+            Plane::from_params(planes[4].0.map(int), Int(planes[4].1)),
+            Plane::from_params(planes[5].0.map(int), Int(planes[5].1)),
+        ]);
+        let float = |v: i64| v as f64;
+        let expected = FrustumCuller::from_planes([
+            Plane::from_params(planes[0].0.map(float), planes[0].1 as f64),
+            Plane::from_params(planes[1].0.map(float), planes[1].1 as f64),
+            Plane::from_params(planes[2].0.map(float), planes[2].1 as f64),
+            Plane::from_params(planes[3].0.map(float), planes[3].1 as f64),
+            Plane::from_params(planes[4].0.map(float), planes[4].1 as f64),
+            Plane::from_params(planes[5].0.map(float), planes[5].1 as f64),
+        ]);
+        assert!(c.has_far_plane());
+
+        let mut partial = 0;
+        for x in -12..12 {
+            for y in -12..12 {
+                for z in -12..12 {
+                    let p = Vector3::new(x, y, z);
+                    let (ip, fp) = (p.map(int), p.map(float));
+                    assert_eq!(expected.test_point(fp), c.test_point(ip));
+                    assert_eq!(
+                        expected.test_sphere(Sphere::from_params(fp, 2.0)),
+                        c.test_sphere(Sphere::from_params(ip, Int(2)))
+                    );
+
+                    let size = Vector3::new(3, 1, 2);
+                    let fb = BoundingBox::from_params(fp, fp + size.map(float));
+                    let ib = BoundingBox::from_params(ip, (p + size).map(int));
+                    let intersection = expected.test_bounding_box(fb);
+                    assert_eq!(intersection, c.test_bounding_box(ib));
+                    assert_eq!(
+                        expected.test_bounding_box_masked(fb, PLANE_MASK_ALL),
+                        c.test_bounding_box_masked(ib, PLANE_MASK_ALL)
+                    );
+                    if intersection == Intersection::Partial {
+                        partial += 1;
+                    }
+                }
+            }
+        }
+        assert!(partial > 0);
+    }
+
+    #[cfg(feature = "serde")]
+    fn round_trip<T>(value: &T)
+    where
+        T: ::serde::Serialize + ::serde::de::DeserializeOwned + PartialEq + ::std::fmt::Debug,
+    {
+        let json = ::serde_json::to_string(value).unwrap();
+        assert_eq!(*value, ::serde_json::from_str::<T>(&json).unwrap());
+        let binary = ::bincode::serialize(value).unwrap();
+        assert_eq!(*value, ::bincode::deserialize::<T>(&binary).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.5,
+            near: 0.1,
+            far: 100.0,
+        });
+        round_trip(&c);
+        round_trip(&FrustumCuller::<f32>::new());
+        round_trip(&c.planes()[3]);
+        round_trip(&BoundingBox::from_params(
+            Vector3::new(-1.0, -2.0, -3.0),
+            Vector3::new(1.0, 2.0, 3.0),
+        ));
+        round_trip(&Sphere::from_params(Vector3::new(0.5, -0.25, 8.0), 2.0));
+        round_trip(&OrientedBoundingBox::from_params(
+            Vector3::new(6.0, 0.0, -5.0),
+            Vector3::new(3.0, 0.1, 0.1),
+            Matrix3::from_angle_y(Deg(45.0)),
+        ));
+        for &intersection in [
             Intersection::Inside,
-            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(1.0, 0.0, -2.0), 0.1))
+            Intersection::Partial,
+            Intersection::Outside,
+        ].iter()
+        {
+            round_trip(&intersection);
+        }
+        round_trip(&DepthRange::OneToZero);
+        round_trip(&FrustumError::InvertedFrustum);
+
+        // the far plane at infinity only survives formats that can represent infinities
+        let infinite = FrustumCuller::from_infinite_perspective_fov(Rad(1.0), 1.0, 0.1);
+        let binary = ::bincode::serialize(&infinite).unwrap();
+        let deserialized: FrustumCuller<f64> = ::bincode::deserialize(&binary).unwrap();
+        assert_eq!(infinite, deserialized);
+        assert!(!deserialized.has_far_plane());
+    }
+
+    #[cfg(feature = "mint")]
+    #[test]
+    fn test_mint() {
+        use cgmath::mint;
+
+        let projection = Matrix4::from(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+        let m: mint::ColumnMatrix4<f64> = projection.into();
+        let c = FrustumCuller::from_mint_matrix(m);
+        assert_eq!(FrustumCuller::from_matrix(projection), c);
+        assert_eq!(FrustumCuller::from_matrix(m.into()), c);
+
+        let point = mint::Vector3::from([0.0, 6.0, -5.0]);
+        assert_eq!(Intersection::Outside, c.test_mint_point(point));
+        assert_eq!(Intersection::Outside, c.test_point(point.into()));
+
+        let sphere = (mint::Vector3::from([1.0, 0.0, -2.0]), 0.1);
+        assert_eq!(
+            Sphere::from_params(Vector3::new(1.0, 0.0, -2.0), 0.1),
+            Sphere::from(sphere)
+        );
+        assert_eq!(Sphere::from(sphere), Sphere::from_mint_params(sphere.0, sphere.1));
+        assert_eq!(Intersection::Inside, c.test_sphere(sphere));
+
+        let aab = (
+            mint::Vector3::from([-1.0, -1.0, -7.0]),
+            mint::Vector3::from([1.0, 1.0, -5.0]),
         );
         assert_eq!(
-            Intersection::Outside,
-            frustum_culling.test_sphere(Sphere::from_params(Vector3::new(4.0, 0.0, -2.0), 0.1))
+            BoundingBox::from_params(Vector3::new(-1.0, -1.0, -7.0), Vector3::new(1.0, 1.0, -5.0)),
+            BoundingBox::from(aab)
         );
+        assert_eq!(BoundingBox::from(aab), BoundingBox::from_mint_params(aab.0, aab.1));
+        assert_eq!(Intersection::Inside, c.test_bounding_box(aab));
+
+        let plane = Plane::from((mint::Vector3::from([0.0, 0.0, -1.0]), -0.1));
+        assert_eq!(Plane::from_params(Vector3::new(0.0, 0.0, -1.0), -0.1), plane);
     }
 
     #[test]
-    fn test_point_in_perspective() {
-        let frustum_culling = FrustumCuller::from_matrix(
-            PerspectiveFov {
-                fovy: Rad(3.14159265 / 2.0),
-                aspect: 1.0,
-                near: 0.1,
-                far: 100.0,
-            }.into(),
-        );
+    fn test_swept_volumes() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
 
+        // a sphere that crosses the view from one side to the other, while being outside of the
+        // frustum at both ends of its path
+        let sphere = Sphere::from_params(Vector3::new(-20.0, 0.0, -10.0), 1.0);
+        let displacement = Vector3::new(40.0, 0.0, 0.0);
+        assert_eq!(Intersection::Outside, c.test_sphere(sphere));
+        assert_eq!(
+            Intersection::Outside,
+            c.test_sphere(Sphere::from_params(sphere.center + displacement, 1.0))
+        );
+        assert_eq!(Intersection::Partial, c.test_swept_sphere(sphere, displacement));
+        assert_eq!(
+            Intersection::Outside,
+            c.test_swept_sphere(sphere, Vector3::new(0.0, 0.0, 40.0))
+        );
         assert_eq!(
             Intersection::Inside,
-            frustum_culling.test_point(Vector3::new(0.0, 0.0, -5.0))
+            c.test_swept_sphere(
+                Sphere::from_params(Vector3::new(0.0, 0.0, -10.0), 1.0),
+                Vector3::new(2.0, -2.0, -20.0)
+            )
         );
+
+        let aab = BoundingBox::from_params(
+            Vector3::new(-22.0, -1.0, -11.0),
+            Vector3::new(-20.0, 1.0, -9.0),
+        );
+        assert_eq!(Intersection::Outside, c.test_bounding_box(aab));
+        assert_eq!(Intersection::Partial, c.test_swept_box(aab, displacement));
         assert_eq!(
             Intersection::Outside,
-            frustum_culling.test_point(Vector3::new(0.0, 6.0, -5.0))
+            c.test_swept_box(aab, Vector3::new(0.0, 0.0, 40.0))
         );
+
+        for x in -12..12 {
+            for y in -12..12 {
+                for z in -12..4 {
+                    let center = Vector3::new(x as f64, y as f64, z as f64) * 4.0;
+                    let displacement = Vector3::new(y as f64, z as f64 * 0.5, x as f64 * 2.0);
+                    let start = Sphere::from_params(center, 1.5);
+                    let end = Sphere::from_params(center + displacement, 1.5);
+
+                    let swept = c.test_swept_sphere(start, displacement);
+                    assert_eq!(c.test_sphere(start), c.test_swept_sphere(start, Vector3::zero()));
+                    if c.test_sphere(start) != Intersection::Outside
+                        || c.test_sphere(end) != Intersection::Outside
+                    {
+                        assert_ne!(Intersection::Outside, swept);
+                    }
+                    if swept == Intersection::Inside {
+                        assert_eq!(Intersection::Inside, c.test_sphere(start));
+                        assert_eq!(Intersection::Inside, c.test_sphere(end));
+                    }
+
+                    // the swept box is never looser than the box enclosing both positions
+                    let half = Vector3::new(1.0, 2.0, 0.5);
+                    let start = BoundingBox::from_params(center - half, center + half);
+                    let end = BoundingBox::from_params(
+                        start.min + displacement,
+                        start.max + displacement,
+                    );
+                    let union = BoundingBox::from_params(
+                        Vector3::new(
+                            start.min.x.min(end.min.x),
+                            start.min.y.min(end.min.y),
+                            start.min.z.min(end.min.z),
+                        ),
+                        Vector3::new(
+                            start.max.x.max(end.max.x),
+                            start.max.y.max(end.max.y),
+                            start.max.z.max(end.max.z),
+                        ),
+                    );
+                    let swept = c.test_swept_box(start, displacement);
+                    assert_eq!(
+                        c.test_bounding_box(start),
+                        c.test_swept_box(start, Vector3::zero())
+                    );
+                    if c.test_bounding_box(start) != Intersection::Outside
+                        || c.test_bounding_box(end) != Intersection::Outside
+                    {
+                        assert_ne!(Intersection::Outside, swept);
+                    }
+                    if swept != Intersection::Outside {
+                        assert_ne!(Intersection::Outside, c.test_bounding_box(union));
+                    }
+                }
+            }
+        }
     }
 
     #[test]
-    fn test_aab_in_ortho() {
-        let mut c = FrustumCuller::from_matrix(
-            Ortho {
-                left: -1.0,
-                right: 1.0,
-                bottom: -1.0,
-                top: 1.0,
-                near: -1.0,
-                far: 1.0,
-            }.into(),
-        );
+    fn test_capsule() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
 
+        let capsule = |start: (f64, f64, f64), end: (f64, f64, f64), radius| {
+            Capsule::from_params(start.into(), end.into(), radius)
+        };
+        assert_eq!(
+            Intersection::Inside,
+            c.test_capsule(capsule((0.0, -1.0, -10.0), (0.0, 1.0, -10.0), 0.5))
+        );
+        // lying across the left plane
         assert_eq!(
             Intersection::Partial,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(-20.0, -2.0, 0.0),
-                Vector3::new(20.0, 2.0, 0.0)
-            ))
+            c.test_capsule(capsule((-20.0, 0.0, -10.0), (0.0, 0.0, -10.0), 0.5))
         );
+        // only the rounded cap at the end reaches into the frustum
         assert_eq!(
-            Intersection::Inside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(-0.5, -0.5, -0.5),
-                Vector3::new(0.5, 0.5, 0.5)
-            ))
+            Intersection::Partial,
+            c.test_capsule(capsule((-20.0, 0.0, -10.0), (-10.5, 0.0, -10.0), 0.5))
         );
         assert_eq!(
             Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(1.1, 0.0, 0.0),
-                Vector3::new(2.0, 2.0, 2.0)
-            ))
+            c.test_capsule(capsule((-20.0, 0.0, -10.0), (-11.0, 0.0, -10.0), 0.5))
+        );
+        // behind the camera, and beyond the far plane
+        assert_eq!(
+            Intersection::Outside,
+            c.test_capsule(capsule((0.0, 0.0, 1.0), (0.0, 0.0, 5.0), 0.5))
+        );
+        assert_eq!(
+            Intersection::Partial,
+            c.test_capsule(capsule((0.0, 0.0, -90.0), (0.0, 0.0, -120.0), 0.5))
         );
 
-        c = FrustumCuller::from_ortho(Ortho {
-            left: -1.0,
-            right: 1.0,
-            bottom: -1.0,
-            top: 1.0,
-            near: -1.0,
-            far: 1.0,
+        // a capsule reduced to a point is a sphere
+        for x in -12..12 {
+            for z in -30..4 {
+                let sphere = Sphere::from_params(Vector3::new(x as f64, 0.5, z as f64 * 4.0), 1.5);
+                assert_eq!(c.test_sphere(sphere), c.test_capsule(sphere));
+            }
+        }
+
+        let capsule: Capsule<f64> = (Vector3::new(1.0, 2.0, 3.0), Vector3::zero(), 0.5).into();
+        assert_eq!(Vector3::new(1.0, 2.0, 3.0), capsule.start);
+        assert_eq!(Capsule::from_params(Vector3::zero(), Vector3::zero(), 0.0), Capsule::new());
+    }
+
+    #[test]
+    fn test_cone() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
         });
+        let cone = |apex: (f64, f64, f64), direction: (f64, f64, f64), range| {
+            Cone::from_params(apex.into(), direction.into(), Rad(0.5), range)
+        };
 
+        assert_eq!(
+            Intersection::Inside,
+            c.test_cone(cone((0.0, 0.0, -5.0), (0.0, 0.0, -1.0), 10.0))
+        );
+        // straddling the near plane: the apex is behind the camera
         assert_eq!(
             Intersection::Partial,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(0.0, 0.0, 0.0),
-                Vector3::new(2.0, 2.0, 2.0)
-            ))
+            c.test_cone(cone((0.0, 0.0, 1.0), (0.0, 0.0, -1.0), 10.0))
+        );
+        // pointing at the camera from just beyond the near plane, with the base behind it
+        assert_eq!(
+            Intersection::Partial,
+            c.test_cone(cone((0.0, 0.0, -1.0), (0.0, 0.0, 2.0), 2.0))
         );
+        // between the camera and the near plane, pointing away from the frustum
         assert_eq!(
             Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(1.1, 0.0, 0.0),
-                Vector3::new(2.0, 2.0, 2.0)
-            ))
+            c.test_cone(cone((0.0, 0.0, -0.05), (0.0, 0.0, 1.0), 5.0))
         );
-
-        c = FrustumCuller::from_matrix(Matrix4::identity());
-
+        // lying on the near plane, and a tiny step in front of it
         assert_eq!(
             Intersection::Partial,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(0.5, 0.5, 0.5),
-                Vector3::new(2.0, 2.0, 2.0)
-            ))
+            c.test_cone(cone((-0.05, 0.0, -0.1), (1.0, 0.0, 0.0), 0.1))
         );
         assert_eq!(
-            Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(1.5, 0.5, 0.5),
-                Vector3::new(2.0, 2.0, 2.0)
+            Intersection::Inside,
+            c.test_cone(Cone::from_params(
+                Vector3::new(-0.02, 0.0, -0.11),
+                Vector3::new(1.0, 0.0, 0.0),
+                Rad(0.001),
+                0.04
             ))
         );
+
+        // pointing away from the frustum, and pointing into it
         assert_eq!(
             Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(-2.5, 0.5, 0.5),
-                Vector3::new(-1.5, 2.0, 2.0)
-            ))
+            c.test_cone(cone((-20.0, 0.0, -10.0), (-1.0, 0.0, 0.0), 10.0))
         );
         assert_eq!(
-            Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(-0.5, -2.5, 0.5),
-                Vector3::new(1.5, -2.0, 2.0)
-            ))
+            Intersection::Partial,
+            c.test_cone(cone((-20.0, 0.0, -10.0), (1.0, 0.0, 0.0), 15.0))
+        );
+
+        // the result agrees with points sampled on the surface of the cone
+        for x in -8..8 {
+            for y in -3..3 {
+                for z in -10..3 {
+                    let apex = Vector3::new(x as f64 * 3.0, y as f64 * 2.0, z as f64 * 4.0);
+                    let direction = Vector3::new(y as f64, x as f64 - 0.5, z as f64 * 0.5 + 2.0);
+                    let cone = Cone::from_params(apex, direction * 3.0, Rad(0.4), 6.0);
+                    let intersection = c.test_cone(cone);
+
+                    let axis = direction.normalize();
+                    let u = axis.cross(Vector3::unit_x() + Vector3::unit_y() * 0.1).normalize();
+                    let v = axis.cross(u);
+                    let mut points = vec![apex];
+                    for i in 0..32 {
+                        let t = f64::consts::PI * i as f64 / 16.0;
+                        let rim = u * t.cos() + v * t.sin();
+                        points.push(cone.base_center() + rim * cone.base_radius());
+                    }
+                    let inside = points
+                        .iter()
+                        .filter(|&&p| c.test_point(p) == Intersection::Inside)
+                        .count();
+
+                    if inside > 0 {
+                        assert_ne!(Intersection::Outside, intersection);
+                    }
+                    if intersection == Intersection::Inside {
+                        assert_eq!(points.len(), inside);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_clip_ray() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        let forward = Ray::from_params(Vector3::zero(), Vector3::new(0.0, 0.0, -1.0));
+        let (enter, exit) = c.clip_ray(forward).unwrap();
+        assert_relative_eq!(enter, 0.1, epsilon = 1e-9);
+        assert_relative_eq!(exit, 100.0, epsilon = 1e-9);
+        assert_eq!(None, c.clip_ray(Ray::from_params(Vector3::zero(), Vector3::unit_z())));
+
+        // the line through the backwards ray still crosses the frustum
+        let backward = Ray::from_params(Vector3::zero(), Vector3::new(0.0, 0.0, 2.0));
+        let (enter, exit) = c.clip_line(backward).unwrap();
+        assert_relative_eq!(enter, -50.0, epsilon = 1e-9);
+        assert_relative_eq!(exit, -0.05, epsilon = 1e-9);
+
+        // starting inside, and parallel to the near plane outside of the frustum
+        let inside = Ray::from_params(Vector3::new(0.0, 0.0, -10.0), Vector3::unit_x());
+        let (enter, exit) = c.clip_ray(inside).unwrap();
+        assert_eq!(0.0, enter);
+        assert_relative_eq!(exit, 10.0, epsilon = 1e-9);
+        assert_eq!(
+            None,
+            c.clip_ray(Ray::from_params(Vector3::new(0.0, 0.0, -0.05), Vector3::unit_x()))
+        );
+
+        // with the far plane at infinity, a ray may never leave the frustum
+        let infinite = FrustumCuller::from_infinite_perspective_fov(
+            Rad(f64::consts::FRAC_PI_2),
+            1.0,
+            0.1,
         );
+        let (enter, exit) = infinite.clip_ray(forward).unwrap();
+        assert_relative_eq!(enter, 0.1, epsilon = 1e-9);
+        assert_eq!(f64::INFINITY, exit);
     }
 
     #[test]
-    fn test_aab_in_perspective() {
+    fn test_clip_segment() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+
+        // crossing the view from left to right
+        let segment = Segment::from_params(
+            Vector3::new(-20.0, 0.0, -10.0),
+            Vector3::new(20.0, 0.0, -10.0),
+        );
+        let (enter, exit) = c.clip_segment(segment).unwrap();
+        assert_relative_eq!(enter, 0.25, epsilon = 1e-9);
+        assert_relative_eq!(exit, 0.75, epsilon = 1e-9);
+        let visible = c.visible_segment(segment).unwrap();
+        assert_relative_eq!(visible.start, Vector3::new(-10.0, 0.0, -10.0), epsilon = 1e-9);
+        assert_relative_eq!(visible.end, Vector3::new(10.0, 0.0, -10.0), epsilon = 1e-9);
+
+        // fully inside, and stopping short of the frustum
+        let inside = Segment::from_params(
+            Vector3::new(0.0, 0.0, -1.0),
+            Vector3::new(1.0, 1.0, -5.0),
+        );
+        assert_eq!(Some((0.0, 1.0)), c.clip_segment(inside));
+        assert_eq!(Some(inside), c.visible_segment(inside));
+        let short = Segment::from_params(
+            Vector3::new(-20.0, 0.0, -10.0),
+            Vector3::new(-11.0, 0.0, -10.0),
+        );
+        assert_eq!(None, c.clip_segment(short));
+        assert_eq!(None, c.visible_segment(short));
+
+        // the clipped interval agrees with the points sampled along the segment
+        for x in -6..6 {
+            for z in -8..2 {
+                let segment: Segment<f64> = (
+                    Vector3::new(x as f64 * 5.0, 1.0, z as f64 * 15.0),
+                    Vector3::new(-x as f64 * 3.0 + 4.0, -2.0, -z as f64 * 10.0 - 60.0),
+                ).into();
+                let interval = c.clip_segment(segment);
+                for i in 0..=100 {
+                    let t = i as f64 / 100.0;
+                    let inside = c.test_point(segment.at(t)) == Intersection::Inside;
+                    match interval {
+                        Some((enter, exit)) if t > enter + 1e-9 && t < exit - 1e-9 => {
+                            assert!(inside)
+                        }
+                        Some((enter, exit)) if t < enter - 1e-9 || t > exit + 1e-9 => {
+                            assert!(!inside)
+                        }
+                        None => assert!(!inside),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_triangle() {
         let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
-            fovy: Rad(3.14159265 / 2.0),
+            fovy: Rad(f64::consts::FRAC_PI_2),
             aspect: 1.0,
             near: 0.1,
             far: 100.0,
         });
+        let v = |x, y, z| Vector3::new(x, y, z);
 
         assert_eq!(
             Intersection::Inside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(0.0, 0.0, -7.0),
-                Vector3::new(1.0, 1.0, -5.0)
-            ))
+            c.test_triangle(v(-1.0, -1.0, -5.0), v(1.0, -1.0, -5.0), v(0.0, 1.0, -6.0))
         );
         assert_eq!(
-            Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(1.1, 0.0, 0.0),
-                Vector3::new(2.0, 2.0, 2.0)
-            ))
+            Intersection::Partial,
+            c.test_triangle(v(-20.0, -1.0, -5.0), v(1.0, -1.0, -5.0), v(0.0, 1.0, -6.0))
         );
         assert_eq!(
             Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(4.0, 4.0, -3.0),
-                Vector3::new(5.0, 5.0, -5.0)
-            ))
+            c.test_triangle(v(-20.0, -1.0, -5.0), v(-10.0, -1.0, -5.0), v(-15.0, 1.0, -6.0))
         );
         assert_eq!(
             Intersection::Outside,
-            c.test_bounding_box(BoundingBox::from_params(
-                Vector3::new(-6.0, -6.0, -2.0),
-                Vector3::new(-1.0, -4.0, -4.0)
-            ))
+            c.test_triangle(v(-1.0, -1.0, 5.0), v(1.0, -1.0, 5.0), v(0.0, 1.0, 6.0))
+        );
+    }
+
+    #[test]
+    fn test_clip_triangle() {
+        let c = FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        });
+        let v = |x, y, z| Vector3::new(x, y, z);
+        let mut out = Vec::new();
+        let mut scratch = Vec::new();
+
+        // fully inside: unchanged
+        let triangle = [v(-1.0, -1.0, -5.0), v(1.0, -1.0, -5.0), v(0.0, 1.0, -6.0)];
+        c.clip_triangle(triangle, &mut out, &mut scratch);
+        assert_eq!(
+            vec![
+                (triangle[0], Vector3::unit_x()),
+                (triangle[1], Vector3::unit_y()),
+                (triangle[2], Vector3::unit_z()),
+            ],
+            out
+        );
+
+        // fully outside
+        c.clip_triangle(
+            [v(-1.0, -1.0, 5.0), v(1.0, -1.0, 5.0), v(0.0, 1.0, 6.0)],
+            &mut out,
+            &mut scratch,
+        );
+        assert!(out.is_empty());
+
+        // one vertex beyond the left plane turns the triangle into a quad
+        let triangle = [v(-20.0, 0.0, -10.0), v(0.0, -2.0, -10.0), v(0.0, 2.0, -10.0)];
+        c.clip_triangle(triangle, &mut out, &mut scratch);
+        assert_eq!(4, out.len());
+        assert_relative_eq!(out[0].0, v(-10.0, -1.0, -10.0), epsilon = 1e-9);
+        assert_relative_eq!(out[0].1, v(0.5, 0.5, 0.0), epsilon = 1e-9);
+        assert_relative_eq!(out[3].0, v(-10.0, 1.0, -10.0), epsilon = 1e-9);
+
+        // big triangles that are clipped by several planes at once
+        let triangles = [
+            [v(-50.0, -50.0, -20.0), v(50.0, -50.0, -20.0), v(0.0, 50.0, -20.0)],
+            [v(-5.0, 0.0, 5.0), v(5.0, 0.0, 5.0), v(0.0, 3.0, -200.0)],
+            [v(0.0, 0.0, -0.05), v(300.0, 10.0, -150.0), v(-40.0, -300.0, -150.0)],
+        ];
+        for triangle in triangles.iter() {
+            c.clip_triangle(*triangle, &mut out, &mut scratch);
+            assert!(out.len() >= 3);
+            for &(position, weights) in out.iter() {
+                assert_relative_eq!(weights.x + weights.y + weights.z, 1.0, epsilon = 1e-9);
+                let interpolated =
+                    triangle[0] * weights.x + triangle[1] * weights.y + triangle[2] * weights.z;
+                assert_relative_eq!(interpolated, position, epsilon = 1e-6);
+                for plane in c.planes().iter() {
+                    assert!(plane.distance_to(position) >= -1e-6);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_clip_polygon() {
+        let c = FrustumCuller::from_ortho(Ortho {
+            left: -1.0,
+            right: 1.0,
+            bottom: -1.0,
+            top: 1.0,
+            near: -1.0,
+            far: 1.0,
+        });
+
+        // a square bigger than the frustum, rotated 45 degrees, is clipped to its section, which
+        // is the square of the frustum since the corners of both lie on the same edges
+        let polygon = [
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Vector3::new(-2.0, 0.0, 0.0),
+            Vector3::new(0.0, -2.0, 0.0),
+        ];
+        let mut vertices: Vec<Vector3<f64>> = Vec::new();
+        let mut weights = Vec::new();
+        let (mut scratch_vertices, mut scratch_weights) = (Vec::new(), Vec::new());
+        c.clip_polygon(
+            &polygon,
+            &mut vertices,
+            &mut weights,
+            &mut scratch_vertices,
+            &mut scratch_weights,
+        );
+        assert_eq!(4, vertices.len());
+        assert_eq!(4 * 4, weights.len());
+        for (vertex, weights) in vertices.iter().zip(weights.chunks(4)) {
+            assert_relative_eq!(vertex.x.abs().max(vertex.y.abs()), 1.0, epsilon = 1e-9);
+            let interpolated = polygon
+                .iter()
+                .zip(weights.iter())
+                .fold(Vector3::zero(), |sum, (&p, &w)| sum + p * w);
+            assert_relative_eq!(interpolated, *vertex, epsilon = 1e-9);
+        }
+
+        let outside = [
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(3.0, 0.0, 0.0),
+            Vector3::new(3.0, 1.0, 0.0),
+        ];
+        c.clip_polygon(
+            &outside,
+            &mut vertices,
+            &mut weights,
+            &mut scratch_vertices,
+            &mut scratch_weights,
+        );
+        assert!(vertices.is_empty() && weights.is_empty());
+
+        // vertices on a plane are kept once, without an intersection of length zero next to them
+        let touching = [
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.5, 0.0),
+        ];
+        c.clip_polygon(
+            &touching,
+            &mut vertices,
+            &mut weights,
+            &mut scratch_vertices,
+            &mut scratch_weights,
+        );
+        assert_eq!(3, vertices.len());
+        assert_eq!(touching[0], vertices[0]);
+        assert_eq!(touching[1], vertices[1]);
+        assert_relative_eq!(Vector3::new(1.0, 0.25, 0.0), vertices[2], epsilon = 1e-9);
+
+        let on_plane = [
+            Vector3::new(1.0, -0.5, 0.0),
+            Vector3::new(1.0, 0.5, 0.0),
+            Vector3::new(0.0, 0.0, 0.0),
+        ];
+        c.clip_polygon(
+            &on_plane,
+            &mut vertices,
+            &mut weights,
+            &mut scratch_vertices,
+            &mut scratch_weights,
         );
+        assert_eq!(&on_plane[..], &vertices[..]);
     }
 }
@@ -0,0 +1,465 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use cgmath::{BaseFloat, Vector3};
+
+use {BoundingBox, FrustumCuller, Intersection, PLANE_MASK_ALL};
+
+/// Handle to an object stored in an `Octree`.
+///
+/// Handles stay valid until the object is removed. After that, the handle may be given to a newly
+/// inserted object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Handle(usize);
+
+/// Loose octree of objects bounded by axis aligned bounding boxes (or spheres), meant for scenes
+/// where objects move every frame.
+///
+/// The bounds of each node are twice the size of its cell, so an object only has to fit in the
+/// cell that contains its center, and moving it rarely requires changing its node. Objects that
+/// are centered outside of the root cell, or are too big for it, are kept in the root node.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Octree<S, T> {
+    max_depth: usize,
+    nodes: Vec<Node<S>>,
+    entries: Vec<Option<Entry<S, T>>>,
+    free: Vec<usize>,
+    /// indices of the nodes that have been pruned, which can be reused
+    free_nodes: Vec<usize>,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Node<S> {
+    center: Vector3<S>,
+    /// half the size of the cell. The loose bounds of the node extend twice as far.
+    half_size: S,
+    /// index of the parent node, or zero for the root
+    parent: usize,
+    /// indices of the child nodes, or zero if a child hasn't been created yet
+    children: [usize; 8],
+    objects: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Entry<S, T> {
+    bounds: BoundingBox<S>,
+    value: T,
+    node: usize,
+}
+
+impl<S: BaseFloat, T> Octree<S, T> {
+    /// Creates an empty octree whose root cell is centered at `center` and extends `half_size`
+    /// along each axis. Nodes are split at most `max_depth` times.
+    pub fn new(center: Vector3<S>, half_size: S, max_depth: usize) -> Self {
+        Self {
+            max_depth,
+            nodes: vec![Node::new(center, half_size, 0)],
+            entries: Vec::new(),
+            free: Vec::new(),
+            free_nodes: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Returns the number of objects.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts an object and returns its handle.
+    pub fn insert<B>(&mut self, bounds: B, value: T) -> Handle
+    where
+        B: Into<BoundingBox<S>>,
+    {
+        let bounds = bounds.into();
+        let node = self.find_node(&bounds);
+        let entry = Entry {
+            bounds,
+            value,
+            node,
+        };
+
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.entries[index] = Some(entry);
+                index
+            }
+            None => {
+                self.entries.push(Some(entry));
+                self.entries.len() - 1
+            }
+        };
+        self.nodes[node].objects.push(index);
+        self.len += 1;
+        Handle(index)
+    }
+
+    /// Removes an object, returning its value, or `None` if the handle is not valid.
+    pub fn remove(&mut self, handle: Handle) -> Option<T> {
+        let entry = self.entries.get_mut(handle.0).and_then(Option::take)?;
+        self.unlink(entry.node, handle.0);
+        self.prune(entry.node);
+        self.free.push(handle.0);
+        self.len -= 1;
+        Some(entry.value)
+    }
+
+    /// Changes the bounds of an object. The object is only moved to a different node if it no
+    /// longer fits in its current one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the handle is not valid.
+    pub fn update<B>(&mut self, handle: Handle, bounds: B)
+    where
+        B: Into<BoundingBox<S>>,
+    {
+        let bounds = bounds.into();
+        let old = self.entry(handle).expect("invalid octree handle").node;
+        let node = self.find_node(&bounds);
+
+        let entry = self.entries[handle.0].as_mut().unwrap();
+        entry.bounds = bounds;
+        entry.node = node;
+
+        if old != node {
+            self.unlink(old, handle.0);
+            self.nodes[node].objects.push(handle.0);
+            self.prune(old);
+        }
+    }
+
+    /// Returns the value of an object.
+    #[inline]
+    pub fn get(&self, handle: Handle) -> Option<&T> {
+        self.entry(handle).map(|entry| &entry.value)
+    }
+
+    /// Returns a mutable reference to the value of an object.
+    #[inline]
+    pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
+        self.entries
+            .get_mut(handle.0)
+            .and_then(Option::as_mut)
+            .map(|entry| &mut entry.value)
+    }
+
+    /// Returns the bounds of an object.
+    #[inline]
+    pub fn bounds(&self, handle: Handle) -> Option<BoundingBox<S>> {
+        self.entry(handle).map(|entry| entry.bounds)
+    }
+
+    /// Appends the handles of the objects that are at least partially inside of the frustum to
+    /// `visible`.
+    ///
+    /// Objects in nodes that are fully inside of the frustum are accepted without being tested.
+    pub fn cull(&self, culler: &FrustumCuller<S>, visible: &mut Vec<Handle>) {
+        // the root may hold objects that lie outside of its loose bounds, so it isn't tested
+        let root = &self.nodes[0];
+        self.cull_objects(root, culler, PLANE_MASK_ALL, visible);
+        for &child in root.children.iter().filter(|&&child| child != 0) {
+            self.cull_node(child, culler, PLANE_MASK_ALL, visible);
+        }
+    }
+
+    fn cull_node(
+        &self,
+        index: usize,
+        culler: &FrustumCuller<S>,
+        mask: u8,
+        visible: &mut Vec<Handle>,
+    ) {
+        let node = &self.nodes[index];
+        let (intersection, mask) = culler.test_bounding_box_masked(node.loose_bounds(), mask);
+        if intersection == Intersection::Outside {
+            return;
+        }
+
+        self.cull_objects(node, culler, mask, visible);
+        for &child in node.children.iter().filter(|&&child| child != 0) {
+            self.cull_node(child, culler, mask, visible);
+        }
+    }
+
+    fn cull_objects(
+        &self,
+        node: &Node<S>,
+        culler: &FrustumCuller<S>,
+        mask: u8,
+        visible: &mut Vec<Handle>,
+    ) {
+        for &index in node.objects.iter() {
+            if mask != 0 {
+                let bounds = self.entries[index].as_ref().unwrap().bounds;
+                if culler.test_bounding_box_masked(bounds, mask).0 == Intersection::Outside {
+                    continue;
+                }
+            }
+            visible.push(Handle(index));
+        }
+    }
+
+    #[inline]
+    fn entry(&self, handle: Handle) -> Option<&Entry<S, T>> {
+        self.entries.get(handle.0).and_then(Option::as_ref)
+    }
+
+    /// Returns the index of the node an object with the given bounds belongs to, creating it (and
+    /// its ancestors) if needed.
+    fn find_node(&mut self, bounds: &BoundingBox<S>) -> usize {
+        let half = S::one() / (S::one() + S::one());
+        let center = (bounds.min + bounds.max) * half;
+        let extent = (bounds.max - bounds.min) * half;
+        let extent = extent.x.max(extent.y).max(extent.z);
+
+        let root = &self.nodes[0];
+        let offset = center - root.center;
+        let inside_root = offset.x.abs() <= root.half_size && offset.y.abs() <= root.half_size
+            && offset.z.abs() <= root.half_size;
+        if !inside_root {
+            return 0;
+        }
+
+        let mut index = 0;
+        for _ in 0..self.max_depth {
+            let (node_center, child_half) = {
+                let node = &self.nodes[index];
+                (node.center, node.half_size * half)
+            };
+            // the object fits in the loose bounds of a child if it's no bigger than its cell
+            let fits = extent <= child_half;
+            if !fits {
+                break;
+            }
+
+            let mut octant = 0;
+            let mut child_center = node_center;
+            for axis in 0..3 {
+                if center[axis] >= node_center[axis] {
+                    octant |= 1 << axis;
+                    child_center[axis] += child_half;
+                } else {
+                    child_center[axis] -= child_half;
+                }
+            }
+
+            let child = self.nodes[index].children[octant];
+            index = if child != 0 {
+                child
+            } else {
+                let node = Node::new(child_center, child_half, index);
+                let child = match self.free_nodes.pop() {
+                    Some(child) => {
+                        self.nodes[child] = node;
+                        child
+                    }
+                    None => {
+                        self.nodes.push(node);
+                        self.nodes.len() - 1
+                    }
+                };
+                self.nodes[index].children[octant] = child;
+                child
+            };
+        }
+        index
+    }
+
+    fn unlink(&mut self, node: usize, index: usize) {
+        let objects = &mut self.nodes[node].objects;
+        let position = objects.iter().position(|&i| i == index).unwrap();
+        objects.swap_remove(position);
+    }
+
+    /// Detaches the given node from the tree if it has neither objects nor children, and then does
+    /// the same with its ancestors, so that moving objects around doesn't leave empty subtrees
+    /// behind. The root is never removed.
+    fn prune(&mut self, mut index: usize) {
+        while index != 0 {
+            let (parent, empty) = {
+                let node = &self.nodes[index];
+                let empty = node.objects.is_empty() && node.children.iter().all(|&c| c == 0);
+                (node.parent, empty)
+            };
+            if !empty {
+                break;
+            }
+
+            for child in self.nodes[parent].children.iter_mut() {
+                if *child == index {
+                    *child = 0;
+                }
+            }
+            self.free_nodes.push(index);
+            index = parent;
+        }
+    }
+}
+
+impl<S: BaseFloat> Node<S> {
+    fn new(center: Vector3<S>, half_size: S, parent: usize) -> Self {
+        Self {
+            center,
+            half_size,
+            parent,
+            children: [0; 8],
+            objects: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn loose_bounds(&self) -> BoundingBox<S> {
+        let loose = self.half_size + self.half_size;
+        let extent = Vector3::new(loose, loose, loose);
+        BoundingBox::from_params(self.center - extent, self.center + extent)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use {BoundingBox, FrustumCuller, Intersection, Sphere};
+
+    use super::{Handle, Octree};
+
+    use cgmath::{PerspectiveFov, Rad, Vector3, prelude::*};
+
+    use std::f64;
+
+    fn culler() -> FrustumCuller<f64> {
+        FrustumCuller::from_perspective_fov(PerspectiveFov {
+            fovy: Rad(f64::consts::FRAC_PI_2),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        })
+    }
+
+    fn brute_force(
+        octree: &Octree<f64, usize>,
+        handles: &[Handle],
+        c: &FrustumCuller<f64>,
+    ) -> Vec<Handle> {
+        let mut visible: Vec<_> = handles
+            .iter()
+            .cloned()
+            .filter(|&h| c.test_bounding_box(octree.bounds(h).unwrap()) != Intersection::Outside)
+            .collect();
+        visible.sort_by_key(|h| h.0);
+        visible
+    }
+
+    fn cull(octree: &Octree<f64, usize>, c: &FrustumCuller<f64>) -> Vec<Handle> {
+        let mut visible = Vec::new();
+        octree.cull(c, &mut visible);
+        visible.sort_by_key(|h| h.0);
+        visible
+    }
+
+    #[test]
+    fn test_cull_matches_brute_force() {
+        let c = culler();
+        let mut octree = Octree::new(Vector3::new(0.0, 0.0, -50.0), 64.0, 6);
+
+        let mut handles = Vec::new();
+        for x in -20i32..20 {
+            for y in -4..4 {
+                for z in -30..4 {
+                    let min = Vector3::new(x as f64 * 3.0, y as f64 * 3.0, z as f64 * 4.0);
+                    let scale = 1 + (x + y + z).abs() % 4;
+                    let size = Vector3::new(1.0, 2.0, 0.5) * scale as f64;
+                    let aab = BoundingBox::from_params(min, min + size);
+                    handles.push(octree.insert(aab, handles.len()));
+                }
+            }
+        }
+        // objects that don't fit in the root cell
+        handles.push(octree.insert(Sphere::from_params(Vector3::zero(), 500.0), handles.len()));
+        handles.push(octree.insert(
+            Sphere::from_params(Vector3::new(0.0, 0.0, -200.0), 150.0),
+            handles.len(),
+        ));
+        assert_eq!(handles.len(), octree.len());
+
+        let visible = cull(&octree, &c);
+        assert!(!visible.is_empty() && visible.len() < handles.len());
+        assert_eq!(brute_force(&octree, &handles, &c), visible);
+
+        // move every other object to the opposite side of the camera, and remove some
+        for (i, &handle) in handles.iter().enumerate().step_by(2) {
+            let aab = octree.bounds(handle).unwrap();
+            let flip = Vector3::new(0.0, 0.0, -aab.min.z - aab.max.z);
+            octree.update(handle, BoundingBox::from_params(aab.min + flip, aab.max + flip));
+            assert_eq!(Some(&i), octree.get(handle));
+        }
+        let removed: Vec<_> = handles.iter().cloned().step_by(3).collect();
+        for &handle in removed.iter() {
+            assert!(octree.remove(handle).is_some());
+            assert_eq!(None, octree.remove(handle));
+        }
+        handles.retain(|h| !removed.contains(h));
+        assert_eq!(handles.len(), octree.len());
+
+        assert_eq!(brute_force(&octree, &handles, &c), cull(&octree, &c));
+    }
+
+    #[test]
+    fn test_reuse_handles() {
+        let mut octree = Octree::new(Vector3::zero(), 10.0, 4);
+        let a = octree.insert(Sphere::from_params(Vector3::new(0.0, 0.0, -5.0), 1.0), 0);
+        assert_eq!(Some(0), octree.remove(a));
+        assert!(octree.is_empty());
+
+        let b = octree.insert(Sphere::from_params(Vector3::new(0.0, 0.0, 0.5), 0.1), 1);
+        assert_eq!(Some(&1), octree.get(b));
+        *octree.get_mut(b).unwrap() = 2;
+        assert_eq!(vec![b], cull(&octree, &FrustumCuller::new()));
+        assert_eq!(Some(&2), octree.get(b));
+    }
+
+    #[test]
+    fn test_prune_empty_nodes() {
+        let mut octree = Octree::new(Vector3::zero(), 64.0, 6);
+        let a = octree.insert(Sphere::from_params(Vector3::new(1.0, 1.0, 1.0), 0.5), 0);
+        let b = octree.insert(Sphere::from_params(Vector3::new(-1.0, 1.0, 1.0), 0.5), 1);
+        let nodes = octree.nodes.len();
+
+        // moving objects around every frame doesn't grow the tree
+        for frame in 0..100 {
+            let offset = Vector3::new(frame as f64 - 50.0, (frame % 7) as f64, -20.0);
+            octree.update(a, Sphere::from_params(offset, 0.5));
+            octree.update(b, Sphere::from_params(-offset, 0.5));
+            let live = octree.nodes.len() - octree.free_nodes.len();
+            assert!(live <= 2 * 6 + 1);
+        }
+        assert!(octree.nodes.len() <= nodes + 2 * 6);
+
+        octree.remove(a);
+        octree.remove(b);
+        assert_eq!(1, octree.nodes.len() - octree.free_nodes.len());
+        assert_eq!([0; 8], octree.nodes[0].children);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid octree handle")]
+    fn test_update_invalid_handle() {
+        let mut octree = Octree::new(Vector3::zero(), 64.0, 6);
+        let a = octree.insert(Sphere::from_params(Vector3::zero(), 0.5), 0);
+        octree.remove(a);
+        octree.update(a, Sphere::from_params(Vector3::new(1.0, 1.0, 1.0), 0.5));
+    }
+}
@@ -0,0 +1,334 @@
+//! SIMD backend for `f32` culling.
+//!
+//! Acceleration is x86-only: the SSE2 path is used on `x86_64`, and on `x86` when SSE2 is
+//! enabled. Every other target, including `aarch64`, uses a scalar fallback that gives the same
+//! results without any speedup.
+
+#[cfg(target_arch = "x86")]
+use std::arch::x86 as arch;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64 as arch;
+
+use {BoundingBox, FrustumCuller, Intersection, Sphere};
+
+/// Number of lanes. The six planes are padded with copies of the first one, which doesn't change
+/// the result of any test.
+const LANES: usize = 8;
+
+/// `f32` frustum culler that tests a volume against all six planes at once using SSE2
+/// instructions. On other targets, including `aarch64` (there is no NEON path), it falls back to
+/// a scalar loop over the planes.
+///
+/// The results are bit-identical to the ones of the equivalent methods of `FrustumCuller<f32>`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SimdFrustumCuller {
+    a: [f32; LANES],
+    b: [f32; LANES],
+    c: [f32; LANES],
+    d: [f32; LANES],
+}
+
+impl SimdFrustumCuller {
+    /// Creates a SIMD culler from the planes of the given frustum culler.
+    pub fn new(culler: &FrustumCuller<f32>) -> Self {
+        let mut simd = Self {
+            a: [0.0; LANES],
+            b: [0.0; LANES],
+            c: [0.0; LANES],
+            d: [0.0; LANES],
+        };
+        // lanes past the last plane, as well as a far plane at infinity, repeat the first plane
+        let tested = culler.plane_mask();
+        for lane in 0..LANES {
+            let plane = if lane < 6 && tested & (1 << lane) != 0 { lane } else { 0 };
+            let (a, b, c, d) = culler.plane_coefficients(plane);
+            simd.a[lane] = a;
+            simd.b[lane] = b;
+            simd.c[lane] = c;
+            simd.d[lane] = d;
+        }
+        simd
+    }
+
+    /// Same as `FrustumCuller::test_sphere`.
+    pub fn test_sphere<T>(&self, sphere: T) -> Intersection
+    where
+        T: Into<Sphere<f32>>,
+    {
+        let sphere = sphere.into();
+        #[cfg(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2")))]
+        unsafe {
+            self.test_sphere_sse2(&sphere)
+        }
+        #[cfg(not(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2"))))]
+        self.test_sphere_fallback(&sphere)
+    }
+
+    /// Same as `FrustumCuller::test_bounding_box`.
+    pub fn test_bounding_box<T>(&self, aab: T) -> Intersection
+    where
+        T: Into<BoundingBox<f32>>,
+    {
+        let aab = aab.into();
+        #[cfg(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2")))]
+        unsafe {
+            self.test_bounding_box_sse2(&aab)
+        }
+        #[cfg(not(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2"))))]
+        self.test_bounding_box_fallback(&aab)
+    }
+
+    #[cfg(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2")))]
+    #[inline]
+    unsafe fn test_sphere_sse2(&self, sphere: &Sphere<f32>) -> Intersection {
+        use self::arch::*;
+
+        let x = _mm_set1_ps(sphere.center.x);
+        let y = _mm_set1_ps(sphere.center.y);
+        let z = _mm_set1_ps(sphere.center.z);
+        let r = _mm_set1_ps(sphere.radius);
+        let neg_r = _mm_set1_ps(-sphere.radius);
+
+        let mut visible = 0;
+        let mut inside = 0;
+        for half in 0..LANES / 4 {
+            let a = _mm_loadu_ps(self.a[4 * half..].as_ptr());
+            let b = _mm_loadu_ps(self.b[4 * half..].as_ptr());
+            let c = _mm_loadu_ps(self.c[4 * half..].as_ptr());
+            let d = _mm_loadu_ps(self.d[4 * half..].as_ptr());
+
+            let dist = _mm_add_ps(
+                _mm_add_ps(
+                    _mm_add_ps(_mm_mul_ps(a, x), _mm_mul_ps(b, y)),
+                    _mm_mul_ps(c, z),
+                ),
+                d,
+            );
+            visible |= _mm_movemask_ps(_mm_cmpge_ps(dist, neg_r)) << (4 * half);
+            inside |= _mm_movemask_ps(_mm_cmpge_ps(dist, r)) << (4 * half);
+        }
+
+        intersection(visible, inside)
+    }
+
+    #[cfg(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2")))]
+    #[inline]
+    unsafe fn test_bounding_box_sse2(&self, aab: &BoundingBox<f32>) -> Intersection {
+        use self::arch::*;
+
+        let zero = _mm_setzero_ps();
+        let sign = _mm_set1_ps(-0.0);
+        let min_x = _mm_set1_ps(aab.min.x);
+        let min_y = _mm_set1_ps(aab.min.y);
+        let min_z = _mm_set1_ps(aab.min.z);
+        let max_x = _mm_set1_ps(aab.max.x);
+        let max_y = _mm_set1_ps(aab.max.y);
+        let max_z = _mm_set1_ps(aab.max.z);
+
+        let mut visible = 0;
+        let mut inside = 0;
+        for half in 0..LANES / 4 {
+            let a = _mm_loadu_ps(self.a[4 * half..].as_ptr());
+            let b = _mm_loadu_ps(self.b[4 * half..].as_ptr());
+            let c = _mm_loadu_ps(self.c[4 * half..].as_ptr());
+            let neg_d = _mm_xor_ps(_mm_loadu_ps(self.d[4 * half..].as_ptr()), sign);
+
+            // per-lane select of the box corners that are farthest along (p) and against (n) the
+            // plane normal
+            let neg_a = _mm_cmplt_ps(a, zero);
+            let neg_b = _mm_cmplt_ps(b, zero);
+            let neg_c = _mm_cmplt_ps(c, zero);
+            let px = _mm_or_ps(_mm_and_ps(neg_a, min_x), _mm_andnot_ps(neg_a, max_x));
+            let py = _mm_or_ps(_mm_and_ps(neg_b, min_y), _mm_andnot_ps(neg_b, max_y));
+            let pz = _mm_or_ps(_mm_and_ps(neg_c, min_z), _mm_andnot_ps(neg_c, max_z));
+            let nx = _mm_or_ps(_mm_and_ps(neg_a, max_x), _mm_andnot_ps(neg_a, min_x));
+            let ny = _mm_or_ps(_mm_and_ps(neg_b, max_y), _mm_andnot_ps(neg_b, min_y));
+            let nz = _mm_or_ps(_mm_and_ps(neg_c, max_z), _mm_andnot_ps(neg_c, min_z));
+
+            let p = _mm_add_ps(
+                _mm_add_ps(_mm_mul_ps(a, px), _mm_mul_ps(b, py)),
+                _mm_mul_ps(c, pz),
+            );
+            let n = _mm_add_ps(
+                _mm_add_ps(_mm_mul_ps(a, nx), _mm_mul_ps(b, ny)),
+                _mm_mul_ps(c, nz),
+            );
+            visible |= _mm_movemask_ps(_mm_cmpge_ps(p, neg_d)) << (4 * half);
+            inside |= _mm_movemask_ps(_mm_cmpge_ps(n, neg_d)) << (4 * half);
+        }
+
+        intersection(visible, inside)
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2"))))]
+    fn test_sphere_fallback(&self, sphere: &Sphere<f32>) -> Intersection {
+        let mut visible = 0;
+        let mut inside = 0;
+        for lane in 0..LANES {
+            let dist = self.a[lane] * sphere.center.x + self.b[lane] * sphere.center.y
+                + self.c[lane] * sphere.center.z + self.d[lane];
+            visible |= ((dist >= -sphere.radius) as i32) << lane;
+            inside |= ((dist >= sphere.radius) as i32) << lane;
+        }
+        intersection(visible, inside)
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", all(target_arch = "x86", target_feature = "sse2"))))]
+    fn test_bounding_box_fallback(&self, aab: &BoundingBox<f32>) -> Intersection {
+        let mut visible = 0;
+        let mut inside = 0;
+        for lane in 0..LANES {
+            let (a, b, c) = (self.a[lane], self.b[lane], self.c[lane]);
+            let (px, nx) = if a < 0.0 {
+                (aab.min.x, aab.max.x)
+            } else {
+                (aab.max.x, aab.min.x)
+            };
+            let (py, ny) = if b < 0.0 {
+                (aab.min.y, aab.max.y)
+            } else {
+                (aab.max.y, aab.min.y)
+            };
+            let (pz, nz) = if c < 0.0 {
+                (aab.min.z, aab.max.z)
+            } else {
+                (aab.max.z, aab.min.z)
+            };
+            visible |= ((a * px + b * py + c * pz >= -self.d[lane]) as i32) << lane;
+            inside |= ((a * nx + b * ny + c * nz >= -self.d[lane]) as i32) << lane;
+        }
+        intersection(visible, inside)
+    }
+}
+
+impl<'a> From<&'a FrustumCuller<f32>> for SimdFrustumCuller {
+    #[inline]
+    fn from(culler: &'a FrustumCuller<f32>) -> Self {
+        Self::new(culler)
+    }
+}
+
+impl From<FrustumCuller<f32>> for SimdFrustumCuller {
+    #[inline]
+    fn from(culler: FrustumCuller<f32>) -> Self {
+        Self::new(&culler)
+    }
+}
+
+/// Combines the per-lane results, where bit `i` of `visible` (`inside`) is set if the volume
+/// passed the outer (inner) test of lane `i`.
+#[inline]
+fn intersection(visible: i32, inside: i32) -> Intersection {
+    let all = (1 << LANES) - 1;
+    if visible != all {
+        Intersection::Outside
+    } else if inside != all {
+        Intersection::Partial
+    } else {
+        Intersection::Inside
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "std"))]
+    use alloc::vec::Vec;
+
+    use {BoundingBox, FrustumCuller, Sphere};
+
+    use super::SimdFrustumCuller;
+
+    use cgmath::{Matrix4, Ortho, PerspectiveFov, Rad, Vector3, prelude::*};
+
+    use std::f32;
+
+    // deterministic pseudo-random numbers in [-range, range)
+    struct Lcg(u32);
+
+    impl Lcg {
+        fn next(&mut self, range: f32) -> f32 {
+            self.0 = self.0.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            ((self.0 >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0) * range
+        }
+
+        fn vector(&mut self, range: f32) -> Vector3<f32> {
+            Vector3::new(self.next(range), self.next(range), self.next(range))
+        }
+    }
+
+    fn cullers() -> Vec<FrustumCuller<f32>> {
+        vec![
+            FrustumCuller::new(),
+            FrustumCuller::from_perspective_fov(PerspectiveFov {
+                fovy: Rad(f32::consts::FRAC_PI_2),
+                aspect: 1.5,
+                near: 0.1,
+                far: 100.0,
+            }),
+            FrustumCuller::from_ortho(Ortho {
+                left: -1.0,
+                right: 3.0,
+                bottom: -2.0,
+                top: 1.0,
+                near: -1.0,
+                far: 10.0,
+            }),
+            FrustumCuller::from_matrix(
+                Matrix4::from(PerspectiveFov {
+                    fovy: Rad(1.0),
+                    aspect: 1.0,
+                    near: 0.5,
+                    far: 50.0,
+                }) * Matrix4::look_at(
+                    (3.0, 2.0, 1.0).into(),
+                    (0.0, 0.0, -10.0).into(),
+                    Vector3::unit_y(),
+                ),
+            ),
+            FrustumCuller::from_infinite_perspective_fov(Rad(1.2), 1.5, 0.1),
+        ]
+    }
+
+    #[test]
+    fn test_bounding_box_matches_scalar() {
+        let mut rng = Lcg(42);
+        for c in cullers() {
+            let simd = SimdFrustumCuller::new(&c);
+            for _ in 0..10_000 {
+                let center = rng.vector(60.0);
+                let half = rng.vector(10.0).map(f32::abs);
+                let aab = BoundingBox::from_params(center - half, center + half);
+                assert_eq!(c.test_bounding_box(aab), simd.test_bounding_box(aab));
+            }
+
+            // degenerate and non-finite boxes
+            let zero = Vector3::zero();
+            let inf = Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+            let nan = Vector3::new(f32::NAN, 0.0, 0.0);
+            for &(min, max) in [(zero, zero), (-inf, inf), (zero, inf), (nan, zero)].iter() {
+                let aab = BoundingBox::from_params(min, max);
+                assert_eq!(c.test_bounding_box(aab), simd.test_bounding_box(aab));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sphere_matches_scalar() {
+        let mut rng = Lcg(7);
+        for c in cullers() {
+            let simd = SimdFrustumCuller::from(c);
+            for _ in 0..10_000 {
+                let sphere = Sphere::from_params(rng.vector(60.0), rng.next(10.0).abs());
+                assert_eq!(c.test_sphere(sphere), simd.test_sphere(sphere));
+            }
+
+            let zero = Vector3::zero();
+            let nan = Vector3::new(f32::NAN, 0.0, 0.0);
+            for &(center, radius) in [(zero, 0.0), (zero, f32::INFINITY), (nan, 1.0)].iter() {
+                let sphere = Sphere::from_params(center, radius);
+                assert_eq!(c.test_sphere(sphere), simd.test_sphere(sphere));
+            }
+        }
+    }
+}